@@ -2,3 +2,8 @@
 #include "ad9361_api.h"
 #include "ad9361_util.h"
 int32_t ad9361_load_gt(struct ad9361_rf_phy *phy, uint64_t freq, uint32_t dest);
+int32_t ad9361_spi_writem(struct spi_desc *spi, uint32_t reg, uint8_t *tbuf, uint32_t num);
+int32_t ad9361_do_calib(struct ad9361_rf_phy *phy, uint32_t cal, int32_t arg);
+int32_t ad9361_mcs(struct ad9361_rf_phy *phy, int32_t step);
+int32_t ad9361_auxdac_set(struct ad9361_rf_phy *phy, uint32_t dac, uint32_t val_mV);
+int32_t ad9361_auxdac_get(struct ad9361_rf_phy *phy, uint32_t dac);
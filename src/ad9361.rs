@@ -1,39 +1,114 @@
 //! An AD9361 RF PHY
 
+use core::cell::UnsafeCell;
 use core::mem;
+use core::ops::RangeInclusive;
 use core::ptr;
-use core::sync::atomic::{AtomicBool, Ordering};
 
-use embedded_hal::{blocking, digital};
 use managed::ManagedSlice;
 use paste::paste;
 
-use crate::{bindings, fir::*, gain_table::*, init, interop, types::*};
+use crate::error::Ad9361Error;
+use crate::registers::*;
+use crate::{
+    bindings, fir::*, gain_table::*, hal, init, interop, latency, synth,
+    types::*,
+};
+
+/// Number of `u32` words of heap used by a C allocation of `size` bytes.
+/// Allocations are made in units of 8 bytes, and allocations shorter than 8
+/// bytes are made from a scratchpad instead
+const fn heap_words(size: usize) -> usize {
+    if size < 8 {
+        0
+    } else {
+        size.div_ceil(8) * 2
+    }
+}
+
+/// Minimum size of the heap passed to [`Ad9361::new`], in `u32` words
+///
+/// This is the peak heap usage of [`Ad9361::init`]: the driver state, its
+/// platform data and reference clock, a clock and its scaling for each clock
+/// of the driver, and the buffer of a multi-byte SPI read, plus a word to
+/// align the start of the heap to 8 bytes. It is computed from
+/// the sizes of the C structures as compiled for the selected device, with
+/// split gain tables enabled. These sizes depend only on the target's pointer
+/// width: the gain tables are not allocated on the heap, and the device
+/// features do not change the structures
+pub const MIN_HEAP_WORDS: usize = {
+    use core::mem::size_of;
+    let clocks = bindings::ad9361_clocks::NUM_AD9361_CLKS as usize;
+
+    heap_words(size_of::<bindings::ad9361_rf_phy>())
+        + heap_words(size_of::<bindings::clk>())
+        + heap_words(size_of::<bindings::ad9361_phy_platform_data>())
+        + clocks
+            * (heap_words(size_of::<bindings::refclk_scale>())
+                + heap_words(size_of::<bindings::clk>()))
+        // command and data of the longest read, freed after each read
+        + heap_words(bindings::MAX_MBYTE_SPI as usize + 2)
+        // alignment of the start of the heap
+        + 1
+};
+
+/// Number of `u32` words in `heap`
+fn heap_len(heap: &ManagedSlice<'_, u32>) -> usize {
+    match heap {
+        ManagedSlice::Borrowed(slice) => slice.len(),
+        #[cfg(feature = "std")]
+        ManagedSlice::Owned(vec) => vec.capacity(),
+    }
+}
 
 /// An AD9361 RF PHY
-pub struct Ad9361<'a, SPI, DELAY, RESETB> {
+pub struct Ad9361<'a, SPI, DELAY, RESETB, SYNC> {
     inner: *mut bindings::ad9361_rf_phy,
     params: init::Ad9361InitParam,
     is_init: bool,
     spi: SPI,
     delay: DELAY,
     resetb: Option<RESETB>,
+    sync: Option<SYNC>,
     heap: ManagedSlice<'a, u32>,
+    context: UnsafeCell<interop::Context>,
+    tx_mute_attenuation: [Option<u32>; 2],
+    init_write_coalescing: bool,
+    cal_sw: [Option<&'a mut dyn hal::OutputPin>; 2],
+    external_lo_hz: [Option<u64>; 2],
     _pinned: core::marker::PhantomPinned,
 }
 
-// We use static pointers and a non-reentrant allocator to interact with the C
-// driver. Therefore there must be at most one instance of AD9361 representation
-// in existance at any one time
-static TAKEN: AtomicBool = AtomicBool::new(false);
-
-impl<'a, SPI, DELAY, RESETB> Ad9361<'a, SPI, DELAY, RESETB> {
+impl<'a, SPI, DELAY, RESETB, SYNC> Ad9361<'a, SPI, DELAY, RESETB, SYNC> {
+    /// Claim the C driver for this instance, making its heap and delay active
+    /// until the returned guard is dropped
+    ///
+    /// Returns `Err(Ad9361Error::Busy)` if another instance is using the
+    /// driver, for example from another thread or an interrupt handler
+    fn activate(&self) -> Result<interop::Active, Ad9361Error> {
+        unsafe { interop::activate(self.context.get()) }.ok_or_else(|| {
+            warn!("AD936x: driver is in use by another instance");
+            Ad9361Error::Busy
+        })
+    }
+    /// Pointer to the driver state, with the C driver claimed for this
+    /// instance until the returned guard is dropped. See
+    /// [`activate`](Self::activate)
+    fn inner_ptr(
+        &self,
+    ) -> Result<(interop::Active, *mut bindings::ad9361_rf_phy), Ad9361Error>
+    {
+        Ok((self.activate()?, self.inner))
+    }
     /// Attempt to free allocated memory in driver
     ///
     /// Returns true if memory was freed
     fn free_inner(&mut self) -> bool {
         if self.is_init && !self.inner.is_null() {
-            let inner_ptr = self.inner;
+            let (_active, inner_ptr) = match self.inner_ptr() {
+                Ok(active) => active,
+                Err(_) => return false,
+            };
 
             let _status = unsafe {
                 cpp! ([
@@ -58,37 +133,226 @@ impl<'a, SPI, DELAY, RESETB> Ad9361<'a, SPI, DELAY, RESETB> {
     pub fn inner_delay(&mut self) -> &mut DELAY {
         &mut self.delay
     }
+    /// Peak heap usage, in `u32` words, since the last call to
+    /// [`init`](Self::init)
+    ///
+    /// This can be used to size the `heap` passed to [`new`](Self::new) for a
+    /// particular configuration
+    pub fn heap_usage(&self) -> usize {
+        unsafe { (*self.context.get()).heap.high_water_mark() }
+    }
+    /// Get the initialisation parameters in effect
+    ///
+    /// These are the parameters passed to [`init`](Self::init), with the
+    /// changes made since by the setters of this driver, and with the clock
+    /// tree, LO frequencies, RF bandwidths, RF ports, gain control modes and
+    /// TX attenuation replaced by the values realised by the device. The
+    /// result can be stored and passed to `init` to restore this
+    /// configuration. The GPIO and SPI descriptors are reset to their
+    /// defaults, as they refer to this instance.
+    ///
+    /// The TX attenuation is that of TX1, as the initialisation parameters
+    /// have a single attenuation for both channels
+    pub fn effective_params(
+        &self,
+    ) -> Result<init::Ad9361InitParam, Ad9361Error> {
+        let mut params = self.params;
+        let p = &mut params.0;
+
+        let (rx_path_clks, tx_path_clks) = self.get_trx_path_clks()?;
+        p.rx_path_clock_frequencies = rx_path_clks;
+        p.tx_path_clock_frequencies = tx_path_clks;
+        p.rx_synthesizer_frequency_hz = self.rx_lo_hz()?;
+        p.tx_synthesizer_frequency_hz = match self.external_lo_hz[1] {
+            Some(hz) => hz,
+            None => self.get_tx_lo_freq()?,
+        };
+        p.rf_rx_bandwidth_hz = self.get_rx_rf_bandwidth()?;
+        p.rf_tx_bandwidth_hz = self.get_tx_rf_bandwidth()?;
+        p.rx_rf_port_input_select = self.get_rx_rf_port_input()?.into();
+        p.tx_rf_port_input_select = self.get_tx_rf_port_output()?.into();
+        p.gc_rx1_mode = self.get_rx_gain_control_mode(RxChannel::Rx1)?.into();
+        p.gc_rx2_mode = self.get_rx_gain_control_mode(RxChannel::Rx2)?.into();
+        p.tx_attenuation_mdB = self.get_tx_attenuation(TxChannel::Tx1)? as i32;
+
+        p.spi_param = Default::default();
+        for gpio in [
+            &mut p.gpio_resetb,
+            &mut p.gpio_sync,
+            &mut p.gpio_cal_sw1,
+            &mut p.gpio_cal_sw2,
+        ] {
+            *gpio = bindings::gpio_init_param {
+                number: -1,
+                ..Default::default()
+            };
+        }
+        Ok(params)
+    }
+    /// Set whether register writes made by [`init`](Ad9361::init) are
+    /// coalesced into multi-byte SPI transfers
+    ///
+    /// When enabled, consecutive writes to adjacent registers are merged into
+    /// a single transfer of up to eight registers, using the address
+    /// auto-decrement of the AD9361. The pending writes are made before any
+    /// read, delay or GPIO change, so the sequence seen by the device is
+    /// unchanged. This reduces the number of SPI transactions, which helps on
+    /// buses with a large overhead per transaction. Disabled by default
+    pub fn set_init_write_coalescing(&mut self, enable: bool) {
+        self.init_write_coalescing = enable;
+    }
+    /// Forget all register values held in the register cache
+    ///
+    /// With the `register_cache` feature, reads of registers that were
+    /// previously written are answered from a shadow copy without a SPI
+    /// transaction. Call this after anything that changes registers behind
+    /// the driver's back, for example a calibration that updates its results
+    /// in registers that were also written by the driver. The cache is
+    /// invalidated by [`init`](Ad9361::init) and [`reset`](Ad9361::reset)
+    #[cfg(feature = "register_cache")]
+    pub fn invalidate_cache(&mut self) {
+        self.context.get_mut().cache.invalidate();
+    }
 }
-impl<'a, SPI, DELAY, RESETB> Drop for Ad9361<'a, SPI, DELAY, RESETB> {
+impl<'a, SPI, DELAY, RESETB, SYNC> Drop
+    for Ad9361<'a, SPI, DELAY, RESETB, SYNC>
+{
     fn drop(&mut self) {
         self.free_inner();
-        assert!(TAKEN.swap(false, Ordering::AcqRel));
+    }
+}
+/// Once initialised, the ENSM state, LO frequencies, sampling rates and
+/// transmit attenuations are read from the device over SPI. Values that cannot
+/// be read are shown as `Err`
+impl<'a, SPI, DELAY, RESETB, SYNC> core::fmt::Debug
+    for Ad9361<'a, SPI, DELAY, RESETB, SYNC>
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        if !self.is_init || self.inner.is_null() {
+            return f.write_str("Ad9361 { uninitialized }");
+        }
+        f.debug_struct("Ad9361")
+            .field("ensm_state", &self.read_ensm_state())
+            .field("rx_lo_freq", &self.get_rx_lo_freq())
+            .field("tx_lo_freq", &self.get_tx_lo_freq())
+            .field("rx_sampling_freq", &self.get_rx_sampling_freq())
+            .field("tx_sampling_freq", &self.get_tx_sampling_freq())
+            .field(
+                "tx_attenuation",
+                &[
+                    self.get_tx_attenuation(TxChannel::Tx1),
+                    self.get_tx_attenuation(TxChannel::Tx2),
+                ],
+            )
+            .finish()
     }
 }
 
-impl<'a, SPI, DELAY, RESETB> Ad9361<'a, SPI, DELAY, RESETB>
+/// An [`Ad9361`] that can be sent to another thread, for example to hand it to
+/// a dedicated radio task after construction
+///
+/// Created by [`Ad9361::into_send`], and converted back with
+/// [`into_inner`](Self::into_inner)
+pub struct SendAd9361<'a, SPI, DELAY, RESETB, SYNC>(
+    Ad9361<'a, SPI, DELAY, RESETB, SYNC>,
+);
+// Safety: see the contract of `Ad9361::into_send`
+unsafe impl<'a, SPI, DELAY, RESETB, SYNC> Send
+    for SendAd9361<'a, SPI, DELAY, RESETB, SYNC>
 where
-    SPI: blocking::spi::Transfer<u8>,
-    DELAY: blocking::delay::DelayMs<u32> + blocking::delay::DelayUs<u32>,
-    RESETB: digital::v2::OutputPin,
+    SPI: Send,
+    DELAY: Send,
+    RESETB: Send,
+    SYNC: Send,
 {
-    /// Construct new AD9361 representation
+}
+impl<'a, SPI, DELAY, RESETB, SYNC> SendAd9361<'a, SPI, DELAY, RESETB, SYNC> {
+    /// Unwrap the [`Ad9361`]
+    pub fn into_inner(self) -> Ad9361<'a, SPI, DELAY, RESETB, SYNC> {
+        self.0
+    }
+}
+impl<'a, SPI, DELAY, RESETB, SYNC> Ad9361<'a, SPI, DELAY, RESETB, SYNC> {
+    /// Wrap this `Ad9361` so that it can be sent to another thread
+    ///
+    /// `Ad9361` is not `Send`, because every call into the C driver first
+    /// switches global state (the active heap, delay, register cache and
+    /// write batch) to this instance. This must be called before
+    /// [`init`](Self::init), which stores pointers into this instance.
     ///
     /// # Panics
     ///
-    /// Panics if an attempt is made to create a second AD9361 interface without
-    /// dropping the first. Static pointers and a non-reentrant allocator are
-    /// used to interact with the C driver, and thus there can be at most one
-    /// instance in existance at a given time.
+    /// Panics if this instance has been initialised
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure that:
+    ///
+    /// * The calibration switch pins passed to
+    ///   [`with_cal_switches`](Self::with_cal_switches), if any, can be used
+    ///   from the receiving thread
+    pub unsafe fn into_send(self) -> SendAd9361<'a, SPI, DELAY, RESETB, SYNC> {
+        assert!(
+            self.inner.is_null(),
+            "into_send() must be called before init()"
+        );
+        SendAd9361(self)
+    }
+}
+
+impl<'a, SPI, DELAY> Ad9361<'a, SPI, DELAY, hal::NoPin, hal::NoPin>
+where
+    SPI: hal::Spi,
+    DELAY: hal::Delay,
+{
+    /// Construct new AD9361 representation without RESETB or SYNC pins
+    ///
+    /// The device is reset through the SPI interface. Equivalent to
+    /// [`new`](Self::new) with `None` for both pins, without having to name
+    /// their types
+    pub fn new_no_reset(
+        spi: SPI,
+        delay: DELAY,
+        heap: impl Into<ManagedSlice<'a, u32>>,
+    ) -> Self {
+        Self::new(spi, delay, None, None, heap)
+    }
+}
+
+impl<'a, SPI, DELAY, RESETB, SYNC> Ad9361<'a, SPI, DELAY, RESETB, SYNC>
+where
+    SPI: hal::Spi,
+    DELAY: hal::Delay,
+    RESETB: hal::OutputPin,
+    SYNC: hal::OutputPin,
+{
+    /// Construct new AD9361 representation
+    ///
+    /// Several instances may exist at once, for example on multi-chip boards.
+    /// Each instance has its own heap and delay, but the C driver is not
+    /// re-entrant: while one instance is inside the driver, methods on any
+    /// other instance return `Err(Ad9361Error::Busy)`, and infallible getters
+    /// panic.
+    ///
+    /// The `sync` pin drives SYNC_IN, and is only required for multi-chip
+    /// synchronisation, see [`mcs`](Self::mcs)
+    ///
+    /// The `heap` must hold at least [`MIN_HEAP_WORDS`] words. This is
+    /// checked by a debug assertion
     pub fn new(
         spi: SPI,
         delay: DELAY,
         resetb: Option<RESETB>,
+        sync: Option<SYNC>,
         heap: impl Into<ManagedSlice<'a, u32>>,
     ) -> Self {
-        if TAKEN.swap(true, Ordering::AcqRel) {
-            panic!("Attempt to create two AD9361 drivers simultaneously!");
-        }
+        let heap = heap.into();
+        let heap_words = heap_len(&heap);
+        debug_assert!(
+            heap_words >= MIN_HEAP_WORDS,
+            "heap of {} words is smaller than MIN_HEAP_WORDS",
+            heap_words
+        );
 
         Self {
             inner: ptr::null_mut(),
@@ -97,13 +361,70 @@ where
             spi,
             delay,
             resetb,
-            heap: heap.into(),
+            sync,
+            heap,
+            context: UnsafeCell::new(interop::Context::new()),
+            tx_mute_attenuation: [None; 2],
+            init_write_coalescing: false,
+            cal_sw: [None, None],
+            external_lo_hz: [None; 2],
             _pinned: core::marker::PhantomPinned,
         }
     }
 
+    /// Construct new AD9361 representation, with a heap array
+    ///
+    /// As [`new`](Self::new), but the heap is an array whose length `N` is
+    /// checked at compile time against [`MIN_HEAP_WORDS`]
+    pub fn with_static_heap<const N: usize>(
+        spi: SPI,
+        delay: DELAY,
+        resetb: Option<RESETB>,
+        sync: Option<SYNC>,
+        heap: &'a mut [u32; N],
+    ) -> Self {
+        let () = HeapSize::<N>::CHECK;
+        Self::new(spi, delay, resetb, sync, &mut heap[..])
+    }
+
+    /// Add the outputs that drive the RF calibration switches
+    ///
+    /// These are the `gpio_cal_sw1` and `gpio_cal_sw2` pins of the no-OS
+    /// driver. They are only needed on boards with RF calibration switches,
+    /// such as the AD-FMCOMMS5, where the switches connect the transmit and
+    /// receive paths of the two devices during calibration. They are not used
+    /// on other boards
+    pub fn with_cal_switches(
+        mut self,
+        cal_sw1: &'a mut dyn hal::OutputPin,
+        cal_sw2: &'a mut dyn hal::OutputPin,
+    ) -> Self {
+        self.cal_sw = [Some(cal_sw1), Some(cal_sw2)];
+        self
+    }
+
     /// Attempt to initialise a AD9361
     ///
+    /// Initialisation runs entirely within the C driver, and blocks for tens
+    /// of milliseconds of SPI traffic and delays. The delays are made through
+    /// the `DELAY` object from within the C driver, so they cannot yield to an
+    /// async executor, and there is no async variant of this method. In an
+    /// async application, initialise the device before starting the executor,
+    /// or from a thread or task that may block. `Ad9361` is not `Send`, so on
+    /// `std` targets it must be created and used on that same thread.
+    ///
+    /// Returns `Err(Ad9361Error::OutOfMemory)` if the heap passed to
+    /// [`new`](Self::new) is smaller than [`MIN_HEAP_WORDS`], or if the
+    /// driver runs out of heap. The driver does not recover from running out
+    /// of heap part way through initialisation, so the size of the heap is
+    /// checked before the driver is called.
+    ///
+    /// Returns `Err(Ad9361Error::NoDevice)` if the product ID read from the
+    /// device is not the AD936x product ID, or if the driver fails to
+    /// initialise the device. The AD9361, AD9364 and AD9363A share one
+    /// product ID, so this detects a missing or unsupported device, but not
+    /// one variant fitted in place of another.
+    ///
     /// # Safety
     ///
     /// Self must not move after the call to `init()`. The `ad9361_rf_phy`
@@ -111,7 +432,10 @@ where
     pub fn init(
         &mut self,
         parameters: init::Ad9361InitParam,
-    ) -> Result<(), i32> {
+    ) -> Result<(), Ad9361Error> {
+        if heap_len(&self.heap) < MIN_HEAP_WORDS {
+            return Err(Ad9361Error::OutOfMemory);
+        }
         self.params = parameters;
 
         // Set pointers to our wrapper methods and parts
@@ -129,18 +453,42 @@ where
                 self.params.0.gpio_resetb.platform_ops = mem::transmute(
                     interop::gpio_set_method::<RESETB> as *mut (),
                 );
-                self.params.0.gpio_resetb.extra = mem::transmute(&resetb);
+                self.params.0.gpio_resetb.extra = mem::transmute(resetb);
+            }
+        }
+        if let Some(sync) = &self.sync {
+            unsafe {
+                self.params.0.gpio_sync.number = 2;
+                self.params.0.gpio_sync.platform_ops =
+                    mem::transmute(interop::gpio_set_method::<SYNC> as *mut ());
+                self.params.0.gpio_sync.extra = mem::transmute(sync);
+            }
+        }
+        let cal_sw_params = [
+            &mut self.params.0.gpio_cal_sw1,
+            &mut self.params.0.gpio_cal_sw2,
+        ];
+        for ((param, cal_sw), number) in
+            cal_sw_params.into_iter().zip(&self.cal_sw).zip(3..)
+        {
+            if let Some(cal_sw) = cal_sw {
+                param.number = number;
+                param.platform_ops =
+                    interop::gpio_set_dyn_method as *const () as *const _;
+                param.extra = cal_sw as *const _ as *mut _;
             }
         }
         // Delay
         unsafe {
-            interop::DELAY_MS =
+            let delay = &mut self.context.get_mut().delay;
+            delay.ms =
                 mem::transmute(interop::delay_ms_method::<DELAY> as *mut ());
-            interop::DELAY_US =
+            delay.us =
                 mem::transmute(interop::delay_us_method::<DELAY> as *mut ());
-            interop::DELAY_OBJECT = mem::transmute(&self.delay);
+            delay.object = mem::transmute(&self.delay);
         }
         // Heap
+        let _active = self.activate()?;
         unsafe {
             let (ptr, len) = match self.heap {
                 ManagedSlice::Borrowed(ref mut slice) => {
@@ -156,8 +504,11 @@ where
 
         // Attempt to free any previous initialisation
         self.free_inner();
+        #[cfg(feature = "register_cache")]
+        self.invalidate_cache();
 
         // Library initialisation
+        self.context.get_mut().batch.enabled = self.init_write_coalescing;
         let inner_ptr = &self.inner;
         let params = &self.params.0;
         let status = unsafe {
@@ -169,60 +520,103 @@ where
                       return ad9361_init(inner_ptr, params);
                   })
         };
+        let batch = &mut self.context.get_mut().batch;
+        let flush_status = batch.flush();
+        batch.enabled = false;
+        let status = if status == 0 { flush_status } else { status };
+
         self.is_init = true;
+        self.tx_mute_attenuation = [None; 2];
+        let params = &self.params.0;
+        self.external_lo_hz = [
+            (params.external_rx_lo_enable != 0)
+                .then_some(params.rx_synthesizer_frequency_hz),
+            (params.external_tx_lo_enable != 0)
+                .then_some(params.tx_synthesizer_frequency_hz),
+        ];
 
+        if self.context.get_mut().heap.exhausted() {
+            return Err(Ad9361Error::OutOfMemory);
+        }
         if status == 0 {
             Ok(())
         } else {
-            Err(status)
+            Err(status.into())
+        }
+    }
+
+    /// Reset the AD9361 to its power-on state
+    ///
+    /// If a RESETB pin was passed to [`new`](Self::new) it is held low for
+    /// 1 ms, and the device is given 1 ms to come out of reset. Otherwise a
+    /// software reset is made through the SPI configuration register.
+    ///
+    /// The driver state is freed, so [`init`](Self::init) must be called again
+    /// before accessing the device. Returns `Err(Ad9361Error::Io)` if the
+    /// RESETB pin or SPI bus fails
+    pub fn reset(&mut self) -> Result<(), Ad9361Error> {
+        self.free_inner();
+
+        if let Some(resetb) = &mut self.resetb {
+            resetb.set_state(false).map_err(|_| Ad9361Error::Io)?;
+            self.delay.delay_ms(1);
+            resetb.set_state(true).map_err(|_| Ad9361Error::Io)?;
+        } else {
+            let [high, low] = REG_SPI_CONF.to_be_bytes();
+            // Single byte write commands
+            for value in [SOFT_RESET, 0] {
+                self.spi
+                    .transfer_in_place(&mut [0x80 | high, low, value])
+                    .map_err(|_| Ad9361Error::Io)?;
+            }
         }
+        self.delay.delay_ms(1);
+        #[cfg(feature = "register_cache")]
+        self.invalidate_cache();
+
+        Ok(())
     }
 }
 
-impl<'a, SPI, DELAY, RESETB> Ad9361<'a, SPI, DELAY, RESETB> {
+impl<'a, SPI, DELAY, RESETB, SYNC> Ad9361<'a, SPI, DELAY, RESETB, SYNC> {
     // -------- RX chain --------
-    ad9361_method!(GET_SET: rx_rf_gain, channel: u8;
-                   i32 => i32; "receive RF gain for the selected channel");
+    ad9361_method!(GET_SET: rx_rf_gain, channel: impl Into<u8> => u8;
+                   i32 => i32; "receive RF gain for the selected channel.
+Channel 0 = RX1, 1 = RX2, see [`RxChannel`]");
     ad9361_method!(GET_SET: rx_rf_bandwidth;
                    u32 => u32; "RX RF bandwidth");
     ad9361_method!(GET_SET: rx_sampling_freq;
                    u32 => u32; "RX sampling frequency");
-    ad9361_method!(GET_SET: rx_lo_freq;
-                   u64 => u64; "RX LO frequency");
+    ad9361_method!(GET: get_rx_lo_freq;
+                   u64 => u64; "Get the RX LO frequency");
 
-    ad9361_method!(SET: set_rx_lo_int_ext;
-                   lo: InternalExternalLO => u8; "Switch between internal and external LO");
-    ad9361_method!(GET: get_rx_rssi, channel: u8;
+    ad9361_method!(GET: get_rx_rssi, channel: impl Into<u8> => u8;
                    bindings::rf_rssi => f32; "Get the RSSI for the selected channel.
-Channel 0 = RX1, 1 = RX2 ");
+Channel 0 = RX1, 1 = RX2, see [`RxChannel`]");
 
-    ad9361_method!(GET_SET: rx_gain_control_mode, channel: u8;
+    ad9361_method!(GET_SET: rx_gain_control_mode, channel: impl Into<u8> => u8;
                    RfGainControlMode => u8; "gain control mode for the selected channel.
-Channel 0 = RX1, 1 = RX2 ");
-    ad9361_method!(SET: set_rx_fir_config;
-                   config: Ad9361RxFir => bindings::AD9361_RXFIRConfig;
-                   "Set the RX FIR configuration");
+Channel 0 = RX1, 1 = RX2, see [`RxChannel`]");
+    ad9361_method!(GET_SET: rx_rfdc_track_en_dis;
+                   bool > InBool => u8; "Enable/disable of RF DC offset tracking");
+    ad9361_method!(GET_SET: rx_bbdc_track_en_dis;
+                   bool > InBool => u8; "Enable/disable of baseband DC offset tracking");
     ad9361_method!(GET_SET: rx_fir_en_dis;
                    bool > InBool => u8; "Enable/disable of the RX FIR filter");
     ad9361_method!(GET_SET: rx_rf_port_input;
                    RxRfPortSelection => u32; "selected RX RF input port");
 
     // -------- TX chain --------
-    ad9361_method!(GET_SET: tx_attenuation, channel: u8;
+    ad9361_method!(GET_SET: tx_attenuation, channel: impl Into<u8> => u8;
                    u32 => u32; "transmit attenuation (in mdB) for the selected channel.
-Channel 0 = TX1, 1 = TX2 ");
+Channel 0 = TX1, 1 = TX2, see [`TxChannel`]");
     ad9361_method!(GET_SET: tx_rf_bandwidth;
                    u32 => u32; "TX RF bandwidth");
     ad9361_method!(GET_SET: tx_sampling_freq;
                    u32 => u32; "TX sampling frequency");
-    ad9361_method!(GET_SET: tx_lo_freq;
-                   u64 => u64; "TX LO frequency");
-
-    ad9361_method!(SET: set_tx_lo_int_ext;
-                   lo: InternalExternalLO => u8; "Switch between internal and external LO");
-    ad9361_method!(SET: set_tx_fir_config;
-                   config: Ad9361TxFir => bindings::AD9361_TXFIRConfig;
-                   "Set the TX FIR configuration");
+    ad9361_method!(GET: get_tx_lo_freq;
+                   u64 => u64; "Get the TX LO frequency");
+
     ad9361_method!(GET_SET: tx_fir_en_dis;
                    bool > InBool => u8; "Enable/disable of the TX FIR filter");
 
@@ -249,441 +643,4811 @@ Channel 0 = TX1, 1 = TX2 ");
     // -------- Misc --------
     ad9361_method!(GET_INFALLIBLE_VAL: ensm_get_state;
                    u8 => EnsmState; "Get Enable State Machine (ENSM) state");
-    ad9361_method!(GET: get_temperature;
-                   i32 > TemperatureX1000 => f32; "Get the temperature in degrees Celsius");
-    ad9361_method!(SET: tx_mute;
-                   mute: bool => u32; "Mute transmit path.
-Note that if you call `tx_mute(TxState::Unmute)` without ever calling `tx_mute(TxState::Mute)`,
-then the TX gain will be set to -0 mdB");
+    ad9361_method!(GET_SET: en_state_machine_mode;
+                   EnsmMode => u32; "Enable State Machine (ENSM) mode.
+
+In the TX, RX, Alert and FDD modes the ENSM is controlled over SPI. In the
+pin control modes the ENSM is controlled by the ENABLE and TXNRX pins, and
+`ensm_enable_pin_pulse_mode_enable` from the initialisation parameters selects
+whether a pulse on ENABLE toggles the state (pulse mode) or the level of ENABLE
+holds the state (level mode). Switching between FDD and TDD operation also
+requires the corresponding initialisation parameters, see
+`Ad9361InitParam::configure_tdd`");
+    ad9361_method!(SET: set_no_ch_mode;
+                   mode: ChannelMode => u8; "Switch between one (1R1T) and two (2R2T)
+receive and transmit channels. The driver reconfigures the baseband clocks, so
+the sampling rates and any FIR filter configurations may need to be re-applied
+afterwards");
+    ad9361_method!(SET: do_calib;
+                   cal: CalibrationType => u32, arg: i32;
+                   "Perform a calibration on demand. The meaning of `arg` depends on
+the calibration type, see [`CalibrationType`]. Pass -1 for the default");
 }
 
-/// Implementation of some methods from ad9361_conv.c
+/// Register access
 ///
-impl<'a, SPI, DELAY, RESETB> Ad9361<'a, SPI, DELAY, RESETB> {
-    /// Set interface timing. Set `tx` for the TX path, clear `tx` for the RX
-    /// path. If the `clock_delay` value has changed since the previous call or
-    /// initial configuration, set `clock_changed`.
-    ///
-    /// # Panics
-    ///
-    /// Panics if `clock_delay` or `data_delay` are >= 16
-    pub fn set_intf_delay(
-        &mut self,
-        tx: bool,
-        clock_delay: u32,
-        data_delay: u32,
-        clock_changed: bool,
-    ) -> Result<(), i32> {
-        assert!(clock_delay < 16);
-        assert!(data_delay < 16);
-
+impl<'a, SPI, DELAY, RESETB, SYNC> Ad9361<'a, SPI, DELAY, RESETB, SYNC> {
+    /// Read a single register
+    fn read_register(&self, address: u16) -> Result<u8, Ad9361Error> {
         assert!(
             !self.inner.is_null(),
             "Must call init() method before accessing ad9361"
         );
-        let inner_ptr = self.inner;
+        let (_active, inner_ptr) = self.inner_ptr()?;
+        let value = unsafe {
+            bindings::ad9361_spi_read((*inner_ptr).spi, address.into())
+        };
+        if value >= 0 {
+            Ok(value as u8)
+        } else {
+            Err(value.into())
+        }
+    }
+    /// Write a single register
+    fn write_register(
+        &self,
+        address: u16,
+        value: u8,
+    ) -> Result<(), Ad9361Error> {
+        assert!(
+            !self.inner.is_null(),
+            "Must call init() method before accessing ad9361"
+        );
+        let (_active, inner_ptr) = self.inner_ptr()?;
         let status = unsafe {
-            if clock_changed {
-                let alert = EnsmState::Alert as u8;
-                bindings::ad9361_ensm_force_state(inner_ptr, alert);
-            }
-            let address = if tx { 0x7 } else { 0x6 };
-            let value = (clock_delay << 4) | data_delay;
-            let status =
-                bindings::ad9361_spi_write((*inner_ptr).spi, address, value);
-            if clock_changed {
-                let fdd = EnsmState::Fdd as u8;
-                bindings::ad9361_ensm_force_state(inner_ptr, fdd);
-            }
-            status
+            bindings::ad9361_spi_write(
+                (*inner_ptr).spi,
+                address.into(),
+                value.into(),
+            )
         };
         if status == 0 {
             Ok(())
         } else {
-            Err(status)
+            Err(status.into())
         }
     }
+    /// Maximum number of registers in a single multi-register transfer
+    const MAX_MBYTE_SPI: usize = 8;
 
-    /// Set the LVDS bias control register 0x03C
+    /// Read register `reg`
+    pub fn spi_read(&mut self, reg: u16) -> Result<u8, Ad9361Error> {
+        self.read_register(reg)
+    }
+    /// Write `val` to register `reg`
+    pub fn spi_write(&mut self, reg: u16, val: u8) -> Result<(), Ad9361Error> {
+        self.write_register(reg, val)
+    }
+    /// Read a block of up to 8 contiguous registers in a single transfer
     ///
-    /// # Panics
+    /// The register address decrements during the transfer, so `values[0]` is
+    /// read from `reg`, `values[1]` from `reg - 1` and so on
+    pub fn spi_read_multiple(
+        &mut self,
+        reg: u16,
+        values: &mut [u8],
+    ) -> Result<(), Ad9361Error> {
+        if values.is_empty() || values.len() > Self::MAX_MBYTE_SPI {
+            return Err(Ad9361Error::InvalidArgument);
+        }
+        assert!(
+            !self.inner.is_null(),
+            "Must call init() method before accessing ad9361"
+        );
+        let (_active, inner_ptr) = self.inner_ptr()?;
+        let status = unsafe {
+            bindings::ad9361_spi_readm(
+                (*inner_ptr).spi,
+                reg.into(),
+                values.as_mut_ptr(),
+                values.len() as u32,
+            )
+        };
+        if status >= 0 {
+            Ok(())
+        } else {
+            Err(status.into())
+        }
+    }
+    /// Write a block of up to 8 contiguous registers in a single transfer
     ///
-    /// Panics if `lvds_bias_m_v` is < 75 or > 450
-    pub fn set_lvds_bias_control(
+    /// The register address decrements during the transfer, so `values[0]` is
+    /// written to `reg`, `values[1]` to `reg - 1` and so on
+    pub fn spi_write_multiple(
         &mut self,
-        rx_on_chip_term: bool,
-        lvds_tx_lo_vcm: bool,
-        lvds_bias_m_v: u32,
-    ) -> Result<(), i32> {
-        assert!(lvds_bias_m_v <= 450);
-        assert!(lvds_bias_m_v >= 75);
-
-        let address = 0x03C;
-        let value = if rx_on_chip_term { 0x20 } else { 0 }
-            | if lvds_tx_lo_vcm { 0x08 } else { 0 }
-            | ((lvds_bias_m_v - 75) / 75);
-
+        reg: u16,
+        values: &[u8],
+    ) -> Result<(), Ad9361Error> {
+        if values.is_empty() || values.len() > Self::MAX_MBYTE_SPI {
+            return Err(Ad9361Error::InvalidArgument);
+        }
         assert!(
             !self.inner.is_null(),
             "Must call init() method before accessing ad9361"
         );
-        let inner_ptr = self.inner;
+        let (_active, inner_ptr) = self.inner_ptr()?;
         let status = unsafe {
-            bindings::ad9361_spi_write((*inner_ptr).spi, address, value)
+            bindings::ad9361_spi_writem(
+                (*inner_ptr).spi,
+                reg.into(),
+                values.as_ptr() as *mut u8, // not modified
+                values.len() as u32,
+            )
         };
         if status == 0 {
             Ok(())
         } else {
-            Err(status)
+            Err(status.into())
         }
     }
 }
 
-/// Gain table methods
+/// Receive gain methods
 ///
-impl<'a, SPI, DELAY, RESETB> Ad9361<'a, SPI, DELAY, RESETB> {
-    /// Set a new gain table
-    pub fn set_gain_table<'g: 's, 's>(
-        &'s mut self,
-        gain_table: &'g mut GainTable,
-    ) -> Result<(), i32> {
+impl<'a, SPI, DELAY, RESETB, SYNC> Ad9361<'a, SPI, DELAY, RESETB, SYNC> {
+    /// Read the receive gain structure for the selected channel
+    fn read_rx_gain(
+        &self,
+        channel: u8,
+    ) -> Result<bindings::rf_rx_gain, Ad9361Error> {
         assert!(
             !self.inner.is_null(),
             "Must call init() method before accessing ad9361"
         );
-        let inner_ptr = self.inner;
+        let (_active, inner_ptr) = self.inner_ptr()?;
+        let mut gain = bindings::rf_rx_gain::default();
+        let rx_id = u32::from(channel) + 1;
+
         let status = unsafe {
-            // set new gt table
-            (*inner_ptr).gt_info = gain_table.set_ptr();
-            (*inner_ptr).current_table = 4_294_967_295;
-            // re-run setup
-            const RX1_RX2: u32 = 3; // both receivers
-            bindings::ad9361_load_gt(inner_ptr, 2_000_000_000, RX1_RX2)
+            bindings::ad9361_get_rx_gain(inner_ptr, rx_id, &mut gain)
         };
         if status == 0 {
-            Ok(())
+            Ok(gain)
         } else {
-            Err(status)
+            Err(status.into())
         }
     }
-}
+    /// Get the receive gain (in dB) for the selected channel.
+    /// Channel 0 = RX1, 1 = RX2
+    pub fn get_rx_gain(
+        &self,
+        channel: impl Into<u8>,
+    ) -> Result<i32, Ad9361Error> {
+        Ok(self.read_rx_gain(channel.into())?.gain_db)
+    }
+    /// Get the receive gain for the selected channel, split into the gain
+    /// table index and the gain of each stage. Channel 0 = RX1, 1 = RX2
+    pub fn get_rx_gain_detailed(
+        &self,
+        channel: impl Into<u8>,
+    ) -> Result<RxGainInfo, Ad9361Error> {
+        Ok(self.read_rx_gain(channel.into())?.into())
+    }
+    /// Get the full RSSI measurement for the selected channel, including the
+    /// preamble RSSI. Channel 0 = RX1, 1 = RX2
+    ///
+    /// `get_rx_rssi` returns only the symbol RSSI in dB
+    pub fn get_rx_rssi_raw(
+        &self,
+        channel: impl Into<u8>,
+    ) -> Result<RfRssi, Ad9361Error> {
+        assert!(
+            !self.inner.is_null(),
+            "Must call init() method before accessing ad9361"
+        );
+        let (_active, inner_ptr) = self.inner_ptr()?;
+        let mut rssi = bindings::rf_rssi::default();
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::*;
-    use embedded_hal::{blocking, digital};
-    use serial_test::serial;
+        let status = unsafe {
+            bindings::ad9361_get_rx_rssi(inner_ptr, channel.into(), &mut rssi)
+        };
+        if status == 0 {
+            Ok(rssi.into())
+        } else {
+            Err(status.into())
+        }
+    }
+    /// Restart the RSSI measurement
+    ///
+    /// This requires `rssi_restart_mode` in the initialisation parameters to
+    /// be 4 (SPI write to register), otherwise the measurement is restarted by
+    /// the event selected there and `Err(Ad9361Error::InvalidArgument)` is
+    /// returned.
+    ///
+    /// The new measurement is available from
+    /// [`get_rx_rssi`](Self::get_rx_rssi) after `rssi_delay + rssi_wait +
+    /// rssi_duration` µs, as set in the initialisation parameters. Until then
+    /// the previous measurement is returned
+    pub fn rssi_restart(&mut self) -> Result<(), Ad9361Error> {
+        let config = self.read_register(REG_RSSI_CONFIG)?;
+        let mode = (config & RSSI_MODE_SELECT_MASK) >> RSSI_MODE_SELECT_SHIFT;
+        if mode != RSSI_MODE_SPI_WRITE {
+            return Err(Ad9361Error::InvalidArgument);
+        }
+        self.write_register(REG_RSSI_CONFIG, config | START_RSSI_MEAS)?;
+        self.write_register(REG_RSSI_CONFIG, config & !START_RSSI_MEAS)
+    }
+    /// Get the gain table entry currently applied to the RX front end of the
+    /// selected channel. Channel 0 = RX1, 1 = RX2
+    ///
+    /// The current gain index is read from the driver, and the LNA, mixer and
+    /// TIA settings at that index are read back from the gain table RAM. The
+    /// `abs_gain` field is the current receive gain reported by the driver.
+    /// With a split gain table this is the total receive gain, not the gain of
+    /// the front end alone
+    pub fn get_rx_gain_bits(
+        &self,
+        channel: impl Into<u8>,
+    ) -> Result<GainEntry, Ad9361Error> {
+        let channel = channel.into();
+        if channel > 1 {
+            return Err(Ad9361Error::InvalidArgument);
+        }
+        let gain = self.read_rx_gain(channel)?;
 
-    use std::collections::HashMap;
+        let config = self.read_register(REG_GAIN_TABLE_CONFIG)?;
+        self.write_register(
+            REG_GAIN_TABLE_CONFIG,
+            START_GAIN_TABLE_CLOCK | receiver_select(channel + 1),
+        )?;
+        let entry = (|| {
+            self.gain_table_settle()?;
+            self.write_register(
+                REG_GAIN_TABLE_ADDRESS,
+                gain.fgt_lmt_index as u8,
+            )?;
+            self.gain_table_settle()?;
+            let reg131 = self.read_register(REG_GAIN_TABLE_READ_DATA1)?;
+            let reg132 = self.read_register(REG_GAIN_TABLE_READ_DATA2)?;
+            let reg133 = self.read_register(REG_GAIN_TABLE_READ_DATA3)?;
 
-    // Dummy reset pin, active low
-    #[derive(Default)]
-    struct DummyResetB {}
-    impl digital::v2::OutputPin for DummyResetB {
-        type Error = ();
+            Ok(GainEntry::new(reg131, reg132, reg133, gain.gain_db as i8))
+        })();
+        // Restoring the write strobe would overwrite the entry
+        self.write_register(REG_GAIN_TABLE_CONFIG, config & !WRITE_GAIN_TABLE)?;
 
-        fn set_low(&mut self) -> Result<(), ()> {
-            trace!("resetb asserted!");
-            Ok(())
-        }
-        fn set_high(&mut self) -> Result<(), ()> {
-            trace!("resetb deasserted!");
+        entry
+    }
+    /// Wait for the gain table RAM after a change of its configuration or
+    /// address. As in the driver, two dummy writes to a read-only register
+    /// take longer than the 3 ADCCLK/16 cycles needed
+    fn gain_table_settle(&self) -> Result<(), Ad9361Error> {
+        self.write_register(REG_GAIN_TABLE_READ_DATA1, 0)?;
+        self.write_register(REG_GAIN_TABLE_READ_DATA1, 0)
+    }
+    /// Set the receive gain (in dB) for the selected channel.
+    /// Channel 0 = RX1, 1 = RX2
+    ///
+    /// The selected channel must be in manual gain control mode, see
+    /// [`set_rx_gain_control_mode`](Self::set_rx_gain_control_mode).
+    /// Otherwise the driver returns an error.
+    pub fn set_rx_gain(
+        &mut self,
+        channel: impl Into<u8>,
+        gain_db: i32,
+    ) -> Result<(), Ad9361Error> {
+        let channel = channel.into();
+        assert!(
+            !self.inner.is_null(),
+            "Must call init() method before accessing ad9361"
+        );
+        let (_active, inner_ptr) = self.inner_ptr()?;
+        let mut gain = bindings::rf_rx_gain {
+            gain_db,
+            ..Default::default()
+        };
+        let rx_id = u32::from(channel) + 1;
+
+        let status = unsafe {
+            bindings::ad9361_set_rx_gain(inner_ptr, rx_id, &mut gain)
+        };
+        if status == 0 {
             Ok(())
+        } else {
+            Err(status.into())
         }
     }
+}
 
-    // Dummy SPI interface that is actually a very shallow implementation of the
-    // AD9361 register interface
-    struct DummySPI {
-        registers: HashMap<u16, u8>,
-    }
-    impl Default for DummySPI {
-        fn default() -> DummySPI {
-            let registers = HashMap::with_capacity(4096);
-            DummySPI { registers }
-        }
+/// Nominal ADC full-scale level, referred to the RX input at 0 dB receive gain
+const ADC_FULL_SCALE_MDBM: i32 = -1_000;
+
+/// Receive level methods
+///
+impl<'a, SPI, DELAY, RESETB, SYNC> Ad9361<'a, SPI, DELAY, RESETB, SYNC> {
+    /// Get the input power (in mdBm) that drives the ADC to full-scale at the
+    /// current gain of the selected channel. Channel 0 = RX1, 1 = RX2
+    ///
+    /// The level is referred to the AD9361 RX input pins, and is calculated
+    /// from the current receive gain and a nominal ADC full-scale level of
+    /// -1 dBm at 0 dB gain. The gain of any external front-end (for example
+    /// an external LNA) is not included, and should be subtracted by the
+    /// caller to obtain a level referred to the antenna.
+    pub fn adc_full_scale_mdbm(
+        &self,
+        channel: impl Into<u8>,
+    ) -> Result<i32, Ad9361Error> {
+        let gain_db = self.get_rx_rf_gain(channel)?;
+
+        Ok(ADC_FULL_SCALE_MDBM - gain_db * 1000)
     }
-    impl blocking::spi::Transfer<u8> for DummySPI {
-        type Error = ();
+    /// Get the input power (in dBm) that drives the ADC to full-scale at the
+    /// current gain of the selected channel. Channel 0 = RX1, 1 = RX2
+    ///
+    /// See [`adc_full_scale_mdbm`](Self::adc_full_scale_mdbm)
+    pub fn adc_full_scale_dbm(
+        &self,
+        channel: impl Into<u8>,
+    ) -> Result<f32, Ad9361Error> {
+        let full_scale = self.adc_full_scale_mdbm(channel)?;
 
-        fn transfer<'w>(
-            &mut self,
-            words: &'w mut [u8],
-        ) -> Result<&'w [u8], Self::Error> {
-            let transaction = transaction::Ad9361Transaction(words);
-            let register = transaction.register();
-            let value = transaction.value();
+        Ok(MilliDecibels::from(full_scale).into())
+    }
+}
 
-            trace!("spi_transaction! {:?} {:x?}", transaction, words);
+/// RX LO frequency at and below which the DC offset low range settings apply
+const DC_OFFSET_LOW_RANGE_MAX_HZ: u64 = 4_000_000_000;
 
-            if transaction.is_write() {
-                // Save value
-                self.registers.insert(register, value);
+/// Receive DC offset methods
+///
+impl<'a, SPI, DELAY, RESETB, SYNC> Ad9361<'a, SPI, DELAY, RESETB, SYNC> {
+    /// Set the DC offset attenuation for RX LO frequencies above 4 GHz
+    /// (`high`) and at or below 4 GHz (`low`)
+    ///
+    /// These are the attenuator settings used by the initialisation and
+    /// tracking RF DC offset calibrations, as set by the
+    /// `dc_offset_attenuation_high_range` and `dc_offset_attenuation_low_range`
+    /// initialisation parameters. Larger values reduce the loop gain of the DC
+    /// offset correction. The setting for the current RX LO frequency is
+    /// applied immediately, and the other is applied by the driver when the LO
+    /// moves into its range.
+    ///
+    /// Valid values are 0 to 31, otherwise `Err` is returned
+    pub fn set_rf_dc_offset_attenuation(
+        &mut self,
+        high: u8,
+        low: u8,
+    ) -> Result<(), Ad9361Error> {
+        if high > RF_DC_OFFSET_ATTEN_MASK || low > RF_DC_OFFSET_ATTEN_MASK {
+            return Err(Ad9361Error::InvalidArgument);
+        }
+        let rx_lo = self.rx_lo_hz()?;
+
+        let (_active, inner_ptr) = self.inner_ptr()?;
+        unsafe {
+            (*(*inner_ptr).pdata).dc_offset_attenuation_high = high;
+            (*(*inner_ptr).pdata).dc_offset_attenuation_low = low;
+        }
+        self.params.0.dc_offset_attenuation_high_range = high;
+        self.params.0.dc_offset_attenuation_low_range = low;
+
+        let atten = if rx_lo <= DC_OFFSET_LOW_RANGE_MAX_HZ {
+            low
+        } else {
+            high
+        };
+        self.write_register(REG_RF_DC_OFFSET_ATTEN, atten)
+    }
+    /// Get the DC offset attenuation for RX LO frequencies above 4 GHz and at
+    /// or below 4 GHz, as (`high`, `low`)
+    ///
+    /// See [`set_rf_dc_offset_attenuation`](Self::set_rf_dc_offset_attenuation)
+    pub fn get_rf_dc_offset_attenuation(
+        &self,
+    ) -> Result<(u8, u8), Ad9361Error> {
+        assert!(
+            !self.inner.is_null(),
+            "Must call init() method before accessing ad9361"
+        );
+        let (_active, inner_ptr) = self.inner_ptr()?;
+        unsafe {
+            let pdata = (*inner_ptr).pdata;
+            Ok((
+                (*pdata).dc_offset_attenuation_high,
+                (*pdata).dc_offset_attenuation_low,
+            ))
+        }
+    }
+}
+
+/// DCXO methods
+///
+impl<'a, SPI, DELAY, RESETB, SYNC> Ad9361<'a, SPI, DELAY, RESETB, SYNC> {
+    /// DCXO tuning is only possible when the DCXO is in use
+    fn check_dcxo(&self) -> Result<(), Ad9361Error> {
+        assert!(
+            !self.inner.is_null(),
+            "Must call init() method before accessing ad9361"
+        );
+        if self.params.0.xo_disable_use_ext_refclk_enable != 0 {
+            Err(Ad9361Error::InvalidArgument)
+        } else {
+            Ok(())
+        }
+    }
+    /// Set the DCXO coarse tuning word, in the range 0 to 63
+    ///
+    /// Returns `Err(Ad9361Error::InvalidArgument)` if an external reference
+    /// clock is used (`xo_disable_use_ext_refclk_enable` is set)
+    pub fn set_dcxo_tune_coarse(
+        &mut self,
+        coarse: u32,
+    ) -> Result<(), Ad9361Error> {
+        self.check_dcxo()?;
+        let (_active, inner_ptr) = self.inner_ptr()?;
+        let status =
+            unsafe { bindings::ad9361_do_dcxo_tune_coarse(inner_ptr, coarse) };
+        if status == 0 {
+            Ok(())
+        } else {
+            Err(status.into())
+        }
+    }
+    /// Get the DCXO coarse tuning word
+    ///
+    /// Returns `Err(Ad9361Error::InvalidArgument)` if an external reference
+    /// clock is used (`xo_disable_use_ext_refclk_enable` is set)
+    pub fn get_dcxo_tune_coarse(&self) -> Result<u32, Ad9361Error> {
+        self.check_dcxo()?;
+        let (_active, inner_ptr) = self.inner_ptr()?;
+        Ok(unsafe { (*(*inner_ptr).pdata).dcxo_coarse })
+    }
+    /// Set the DCXO fine tuning word, in the range 0 to 8191
+    ///
+    /// Returns `Err(Ad9361Error::InvalidArgument)` if an external reference
+    /// clock is used (`xo_disable_use_ext_refclk_enable` is set)
+    pub fn set_dcxo_tune_fine(&mut self, fine: u32) -> Result<(), Ad9361Error> {
+        self.check_dcxo()?;
+        let (_active, inner_ptr) = self.inner_ptr()?;
+        let status =
+            unsafe { bindings::ad9361_do_dcxo_tune_fine(inner_ptr, fine) };
+        if status == 0 {
+            Ok(())
+        } else {
+            Err(status.into())
+        }
+    }
+    /// Get the DCXO fine tuning word
+    ///
+    /// Returns `Err(Ad9361Error::InvalidArgument)` if an external reference
+    /// clock is used (`xo_disable_use_ext_refclk_enable` is set)
+    pub fn get_dcxo_tune_fine(&self) -> Result<u32, Ad9361Error> {
+        self.check_dcxo()?;
+        let (_active, inner_ptr) = self.inner_ptr()?;
+        Ok(unsafe { (*(*inner_ptr).pdata).dcxo_fine })
+    }
+}
+
+/// Clock output methods
+///
+impl<'a, SPI, DELAY, RESETB, SYNC> Ad9361<'a, SPI, DELAY, RESETB, SYNC> {
+    /// Select the signal on the CLK_OUT pin
+    ///
+    /// The initialisation parameters are updated too, so the mode is kept if
+    /// the device is initialised again
+    pub fn set_clk_output_mode(
+        &mut self,
+        mode: ClkOutputMode,
+    ) -> Result<(), Ad9361Error> {
+        let value = self.read_register(REG_BBPLL)?;
+        let value = match mode {
+            ClkOutputMode::Disable => value & !CLKOUT_ENABLE,
+            _ => {
+                let select = (u32::from(mode) - 1) as u8;
+                (value & !CLKOUT_SELECT_MASK)
+                    | (select << CLKOUT_SELECT_SHIFT)
+                    | CLKOUT_ENABLE
+            }
+        };
+        self.write_register(REG_BBPLL, value)?;
+        self.params.0.clk_output_mode_select = mode.into();
+
+        Ok(())
+    }
+    /// Get the signal on the CLK_OUT pin
+    pub fn get_clk_output_mode(&self) -> Result<ClkOutputMode, Ad9361Error> {
+        let value = self.read_register(REG_BBPLL)?;
+        if value & CLKOUT_ENABLE == 0 {
+            return Ok(ClkOutputMode::Disable);
+        }
+        let select = (value & CLKOUT_SELECT_MASK) >> CLKOUT_SELECT_SHIFT;
+
+        Ok(ClkOutputMode::from(u32::from(select) + 1))
+    }
+}
+
+/// Number of taps in the RX and TX half-band filters
+const RHB_TAPS: [u32; 3] = [47, 11, 5];
+const THB_TAPS: [u32; 3] = [71, 7, 5];
+
+/// Latency methods
+///
+impl<'a, SPI, DELAY, RESETB, SYNC> Ad9361<'a, SPI, DELAY, RESETB, SYNC> {
+    /// Group delay of the filter stages enabled in `filter_ctrl`
+    fn filter_chain_latency(
+        &self,
+        filter_ctrl: u16,
+        filter_config: u16,
+        hb_taps: &[u32; 3],
+    ) -> Result<u32, Ad9361Error> {
+        // A bypassed stage has no delay
+        const BYPASS: latency::FilterStage =
+            latency::FilterStage { taps: 1, ratio: 1 };
+        let stage = |enabled: bool, taps, ratio| {
+            if enabled {
+                latency::FilterStage { taps, ratio }
             } else {
-                for i in 0..transaction.length() {
-                    let reg = register + i as u16;
-                    // Recall value (except for options below)
-                    if let Some(value) = self.registers.get(&reg) {
-                        // Recall
-                        words[2 + i] = *value;
-                    }
-                }
+                BYPASS
+            }
+        };
+
+        let ctrl = self.read_register(filter_ctrl)?;
+        let hb3 = (ctrl & HB3_ENABLE_MASK) >> HB3_ENABLE_SHIFT;
+        let fir = ctrl & FIR_ENABLE_MASK;
+        let fir_taps = if fir != 0 {
+            let config = self.read_register(filter_config)?;
+            ((u32::from(config >> FIR_NUM_TAPS_SHIFT) & 7) + 1) * 16
+        } else {
+            1
+        };
+
+        // Ordered from the converter
+        let stages = [
+            stage(hb3 != 0, hb_taps[2], if hb3 == 1 { 2 } else { 3 }),
+            stage(ctrl & HB2_EN != 0, hb_taps[1], 2),
+            stage(ctrl & HB1_EN != 0, hb_taps[0], 2),
+            stage(fir != 0, fir_taps, 1 << fir.saturating_sub(1)),
+        ];
+
+        Ok(latency::group_delay_samples(&stages))
+    }
+
+    /// Get the latency of the RX and TX signal paths, as (`rx_latency`,
+    /// `tx_latency`) in samples at the RX and TX sample rates
+    ///
+    /// The latency is calculated from the group delay of the enabled
+    /// half-band filters (HB1, HB2, HB3 / DEC3 / INT3) and the programmable
+    /// FIR filter, which are assumed to be linear-phase. The half-band filters
+    /// have 47, 11 and 5 taps in the RX path and 71, 7 and 5 taps in the TX
+    /// path, and the FIR filter length is read from the device.
+    ///
+    /// Pipeline delays in the converters, the data interface and any FPGA
+    /// logic are not included. For precise time alignment, measure the total
+    /// latency with a loopback.
+    pub fn path_latency_samples(&self) -> Result<(u32, u32), Ad9361Error> {
+        let rx = self.filter_chain_latency(
+            REG_RX_ENABLE_FILTER_CTRL,
+            REG_RX_FILTER_CONFIG,
+            &RHB_TAPS,
+        )?;
+        let tx = self.filter_chain_latency(
+            REG_TX_ENABLE_FILTER_CTRL,
+            REG_TX_FILTER_CONF,
+            &THB_TAPS,
+        )?;
+
+        Ok((rx, tx))
+    }
+}
+
+/// Auxiliary ADC methods
+///
+impl<'a, SPI, DELAY, RESETB, SYNC> Ad9361<'a, SPI, DELAY, RESETB, SYNC> {
+    /// Select the AuxADC source, and power up the AuxADC
+    ///
+    /// The AuxADC has a single input, the AUXADC pin, which requires an
+    /// external connection. See [`AuxAdcSource`]
+    pub fn set_aux_adc_source(
+        &mut self,
+        source: AuxAdcSource,
+    ) -> Result<(), Ad9361Error> {
+        match source {
+            AuxAdcSource::External => {
+                let config = self.read_register(REG_AUXADC_CONFIG)?;
+                self.write_register(
+                    REG_AUXADC_CONFIG,
+                    config & !AUX_ADC_POWER_DOWN,
+                )
+            }
+        }
+    }
+    /// Get the AuxADC source, or `None` if the AuxADC is powered down
+    pub fn get_aux_adc_source(
+        &self,
+    ) -> Result<Option<AuxAdcSource>, Ad9361Error> {
+        let config = self.read_register(REG_AUXADC_CONFIG)?;
+
+        if config & AUX_ADC_POWER_DOWN == 0 {
+            Ok(Some(AuxAdcSource::External))
+        } else {
+            Ok(None)
+        }
+    }
+    /// Full scale of the AuxADC in millivolts, equal to the nominal
+    /// VDDA1P3_BB supply
+    const AUX_ADC_FULL_SCALE_MV: u32 = 1300;
+    /// Read the raw 12-bit AuxADC code for the voltage on the AUXADC pin
+    ///
+    /// The AuxADC is powered up for the measurement if required, and returned
+    /// to its previous state afterwards. The sampling clock and decimation are
+    /// set by the `aux_adc_rate` and `aux_adc_decimation` initialisation
+    /// parameters. After powering up, the read waits for one conversion of
+    /// `aux_adc_decimation` clocks at `aux_adc_rate` to complete
+    pub fn get_auxadc(&mut self) -> Result<u32, Ad9361Error>
+    where
+        DELAY: hal::Delay,
+    {
+        let config = self.read_register(REG_AUXADC_CONFIG)?;
+        if config & AUX_ADC_POWER_DOWN != 0 {
+            self.write_register(
+                REG_AUXADC_CONFIG,
+                config & !AUX_ADC_POWER_DOWN,
+            )?;
+            let rate = u64::from(self.params.0.aux_adc_rate.max(1));
+            let decimation = u64::from(self.params.0.aux_adc_decimation);
+            let conversion_us = (decimation * 1_000_000).div_ceil(rate);
+            self.delay.delay_us(conversion_us as u32);
+        }
+
+        // The word is latched when the LSB register is read
+        let lsb = self.read_register(REG_AUXADC_WORD_LSB);
+        let msb = self.read_register(REG_AUXADC_WORD_MSB);
+        self.write_register(REG_AUXADC_CONFIG, config)?;
+
+        Ok((u32::from(msb?) << 4) | u32::from(lsb? & AUXADC_WORD_LSB_MASK))
+    }
+    /// Measure the voltage on the AUXADC pin in millivolts
+    ///
+    /// The AuxADC is ratiometric to the VDDA1P3_BB supply, and the code is
+    /// scaled as `mV = code * 1300 / 4096`, assuming a nominal 1.3V supply.
+    /// The result is truncated to a whole millivolt. Any deviation of
+    /// VDDA1P3_BB from 1.3V is a gain error in the result, and the AuxADC
+    /// also has offset and gain errors of its own, so for accurate
+    /// measurements (such as of a temperature diode) calibrate the raw code
+    /// from [`get_auxadc`](Self::get_auxadc) against known inputs.
+    ///
+    /// The input range is 0.05V to VDDA1P3_BB - 0.05V. A supply rail must be
+    /// brought to the AUXADC pin through an external divider to be measured;
+    /// there is no internal path from the supplies to the AuxADC
+    pub fn get_auxadc_mv(&mut self) -> Result<u16, Ad9361Error>
+    where
+        DELAY: hal::Delay,
+    {
+        let code = self.get_auxadc()?;
+        Ok((code * Self::AUX_ADC_FULL_SCALE_MV / 4096) as u16)
+    }
+    /// Measure the voltage on the AUXADC pin in volts
+    ///
+    /// See [`get_auxadc_mv`](Self::get_auxadc_mv)
+    pub fn get_auxadc_voltage(&mut self) -> Result<f32, Ad9361Error>
+    where
+        DELAY: hal::Delay,
+    {
+        let code = self.get_auxadc()?;
+        Ok(code as f32 * Self::AUX_ADC_FULL_SCALE_MV as f32 / 4096. / 1000.)
+    }
+}
+
+/// Auxiliary DAC methods
+///
+impl<'a, SPI, DELAY, RESETB, SYNC> Ad9361<'a, SPI, DELAY, RESETB, SYNC> {
+    /// Range of the AuxDAC output in millivolts. The output is also limited
+    /// by the VDD_GPO supply
+    const AUX_DAC_MV: core::ops::RangeInclusive<u32> = 306..=3639;
+    /// Set the output of an AuxDAC in millivolts
+    ///
+    /// The AuxDAC has a 10-bit span with either a 1V or a 2.5V reference,
+    /// selected by the driver, giving an output range of 306 mV to 3639 mV.
+    /// Returns `Err(Ad9361Error::InvalidArgument)` if `millivolts` is outside
+    /// this range
+    pub fn set_auxdac(
+        &mut self,
+        dac: AuxDac,
+        millivolts: u32,
+    ) -> Result<(), Ad9361Error> {
+        if !Self::AUX_DAC_MV.contains(&millivolts) {
+            return Err(Ad9361Error::InvalidArgument);
+        }
+        assert!(
+            !self.inner.is_null(),
+            "Must call init() method before accessing ad9361"
+        );
+        let (_active, inner_ptr) = self.inner_ptr()?;
+        let status = unsafe {
+            bindings::ad9361_auxdac_set(inner_ptr, dac.into(), millivolts)
+        };
+        if status == 0 {
+            Ok(())
+        } else {
+            Err(status.into())
+        }
+    }
+    /// Get the output of an AuxDAC in millivolts, as last set
+    pub fn get_auxdac(&self, dac: AuxDac) -> Result<u32, Ad9361Error> {
+        assert!(
+            !self.inner.is_null(),
+            "Must call init() method before accessing ad9361"
+        );
+        let (_active, inner_ptr) = self.inner_ptr()?;
+        let value =
+            unsafe { bindings::ad9361_auxdac_get(inner_ptr, dac.into()) };
+        if value >= 0 {
+            Ok(value as u32)
+        } else {
+            Err(value.into())
+        }
+    }
+}
+
+/// General purpose output methods
+///
+/// The four GPO pins are either under manual control, when the
+/// `gpo_manual_mode_enable` initialisation parameter is set, or toggled
+/// automatically by the ENSM. In the automatic mode each pin rests in the
+/// state set by `gpoX_inactive_state_high_enable`, and is inverted while the
+/// ENSM is in the Rx or Tx state if `gpoX_slave_rx_enable` or
+/// `gpoX_slave_tx_enable` is set, after the `gpoX_rx_delay_us` or
+/// `gpoX_tx_delay_us` delay. The slave settings have no effect in manual mode.
+impl<'a, SPI, DELAY, RESETB, SYNC> Ad9361<'a, SPI, DELAY, RESETB, SYNC> {
+    /// Number of GPO pins
+    const GPOS: u8 = 4;
+    /// Set the level of a GPO pin, 0..=3, in manual mode
+    ///
+    /// Returns `Err(Ad9361Error::InvalidArgument)` if the GPOs are not in
+    /// manual mode, since the ENSM is then in control of the pins
+    pub fn set_gpo(&mut self, gpo: u8, value: bool) -> Result<(), Ad9361Error> {
+        if gpo >= Self::GPOS {
+            return Err(Ad9361Error::InvalidArgument);
+        }
+        if self.read_register(REG_EXTERNAL_LNA_CTRL)? & GPO_MANUAL_SELECT == 0 {
+            return Err(Ad9361Error::InvalidArgument);
+        }
+
+        let bit = 1 << (GPO_MANUAL_CTRL_SHIFT + gpo);
+        let force = self.read_register(REG_GPO_FORCE_AND_INIT)?;
+        let force = if value { force | bit } else { force & !bit };
+        self.write_register(REG_GPO_FORCE_AND_INIT, force)
+    }
+    /// Get the level of a GPO pin, 0..=3, as set in manual mode
+    pub fn get_gpo(&self, gpo: u8) -> Result<bool, Ad9361Error> {
+        if gpo >= Self::GPOS {
+            return Err(Ad9361Error::InvalidArgument);
+        }
+        let force = self.read_register(REG_GPO_FORCE_AND_INIT)?;
+        Ok(force & (1 << (GPO_MANUAL_CTRL_SHIFT + gpo)) != 0)
+    }
+}
+
+/// Control output methods
+///
+/// The eight CTRL_OUT pins are driven from one of a set of internal signal
+/// groups, selected by the control output pointer. Each pointer value routes a
+/// fixed group of eight signals, such as calibration done flags, gain lock and
+/// overload detectors, to CTRL_OUT\[7:0\]. The signal on each pin for each
+/// pointer value is listed in the control output table of the AD9361
+/// reference manual (UG-570). Individual signals cannot be routed to a chosen
+/// pin.
+impl<'a, SPI, DELAY, RESETB, SYNC> Ad9361<'a, SPI, DELAY, RESETB, SYNC> {
+    /// Select the signal group on the CTRL_OUT pins
+    ///
+    /// `index` is the control output pointer, and bit `n` of `enable_mask`
+    /// enables CTRL_OUT`n`. Disabled pins are driven low. The setting is also
+    /// stored in the initialisation parameters, so is retained if the device
+    /// is re-initialised
+    pub fn configure_ctrl_outs(
+        &mut self,
+        index: u8,
+        enable_mask: u8,
+    ) -> Result<(), Ad9361Error> {
+        assert!(
+            !self.inner.is_null(),
+            "Must call init() method before accessing ad9361"
+        );
+        let (_active, inner_ptr) = self.inner_ptr()?;
+        unsafe {
+            (*(*inner_ptr).pdata).ctrl_outs_ctrl.index = index;
+            (*(*inner_ptr).pdata).ctrl_outs_ctrl.en_mask = enable_mask;
+        }
+        self.params.0.ctrl_outs_index = index;
+        self.params.0.ctrl_outs_enable_mask = enable_mask;
+
+        self.write_register(REG_CTRL_OUTPUT_POINTER, index)?;
+        self.write_register(REG_CTRL_OUTPUT_ENABLE, enable_mask)
+    }
+    /// Get the control output pointer and enable mask, as (`index`,
+    /// `enable_mask`)
+    ///
+    /// See [`configure_ctrl_outs`](Self::configure_ctrl_outs)
+    pub fn get_ctrl_outs(&self) -> Result<(u8, u8), Ad9361Error> {
+        let index = self.read_register(REG_CTRL_OUTPUT_POINTER)?;
+        let enable_mask = self.read_register(REG_CTRL_OUTPUT_ENABLE)?;
+
+        Ok((index, enable_mask))
+    }
+}
+
+/// ENSM methods
+///
+impl<'a, SPI, DELAY, RESETB, SYNC> Ad9361<'a, SPI, DELAY, RESETB, SYNC> {
+    /// Set whether the ENSM returns to the Alert state at the end of a burst
+    ///
+    /// This sets the To Alert bit of the ENSM configuration. In TDD mode, when
+    /// a TX or RX burst ends (the ENABLE pulse in pulse mode, or ENABLE falling
+    /// in level mode) the ENSM passes through the TX or RX flush state and then
+    /// moves to Alert if enabled, or to Wait if disabled. In FDD mode the same
+    /// choice applies when the FDD state is exited. Returning to Alert keeps
+    /// the synthesizers powered, so the next burst starts without waiting for
+    /// them to lock.
+    ///
+    /// The driver enables this every time it changes the ENSM state, including
+    /// in [`init`](Self::init) and
+    /// [`set_en_state_machine_mode`](Self::set_en_state_machine_mode), so
+    /// disabling it must be repeated after such calls
+    pub fn set_ensm_auto_return(
+        &mut self,
+        enable: bool,
+    ) -> Result<(), Ad9361Error> {
+        let config = self.read_register(REG_ENSM_CONFIG_1)?;
+        let config = if enable {
+            config | TO_ALERT
+        } else {
+            config & !TO_ALERT
+        };
+        self.write_register(REG_ENSM_CONFIG_1, config)
+    }
+    /// Get whether the ENSM returns to the Alert state at the end of a burst
+    ///
+    /// See [`set_ensm_auto_return`](Self::set_ensm_auto_return)
+    pub fn get_ensm_auto_return(&self) -> Result<bool, Ad9361Error> {
+        let config = self.read_register(REG_ENSM_CONFIG_1)?;
+        Ok(config & TO_ALERT != 0)
+    }
+    /// Read the ENSM state, returning `Err` instead of panicking if it cannot
+    /// be read
+    fn read_ensm_state(&self) -> Result<EnsmState, Ad9361Error> {
+        let state = self.read_register(REG_STATE)? & ENSM_STATE_MASK;
+        Ok(state.into())
+    }
+    /// Force the ENSM into `state`
+    ///
+    /// This bypasses the normal ENSM control path: the state is forced over
+    /// SPI regardless of the ENSM mode and the ENABLE and TXNRX pins, until
+    /// the ENSM is forced into another state. The Sleep/Wait, Alert, Tx, Rx
+    /// and Fdd states can be forced. Tx and Rx are only reachable in TDD
+    /// mode, and Fdd only in FDD mode. Otherwise
+    /// `Err(Ad9361Error::InvalidArgument)` is returned
+    pub fn ensm_force_state(
+        &mut self,
+        state: EnsmState,
+    ) -> Result<(), Ad9361Error> {
+        let fdd = self.read_register(REG_ENSM_MODE)? & FDD_MODE != 0;
+        let reachable = match state {
+            EnsmState::SleepOrWait | EnsmState::Alert => true,
+            EnsmState::Tx | EnsmState::Rx => !fdd,
+            EnsmState::Fdd => fdd,
+            _ => false,
+        };
+        if !reachable {
+            return Err(Ad9361Error::InvalidArgument);
+        }
+        let (_active, inner_ptr) = self.inner_ptr()?;
+
+        unsafe {
+            bindings::ad9361_ensm_force_state(inner_ptr, state.into());
+        }
+        Ok(())
+    }
+    /// Force the ENSM into `state`, and restore the current state when the
+    /// returned guard is dropped
+    ///
+    /// The guard dereferences to this `Ad9361`, so the device can be accessed
+    /// through it while the state is forced. See
+    /// [`ensm_force_state`](Self::ensm_force_state)
+    pub fn force_state_scoped(
+        &mut self,
+        state: EnsmState,
+    ) -> Result<EnsmGuard<'_, 'a, SPI, DELAY, RESETB, SYNC>, Ad9361Error> {
+        let previous = self.ensm_get_state();
+        self.ensm_force_state(state)?;
+
+        Ok(EnsmGuard {
+            ad9361: self,
+            previous,
+        })
+    }
+    /// Poll the ENSM state every 100 µs until it reaches `target`
+    ///
+    /// Each state change seen while polling is logged at debug level.
+    /// Returns `Err(Ad9361Error::TimedOut)` if `target` is not reached within
+    /// `timeout_ms` milliseconds
+    pub fn wait_for_ensm_state(
+        &mut self,
+        target: EnsmState,
+        timeout_ms: u32,
+    ) -> Result<(), Ad9361Error>
+    where
+        DELAY: hal::Delay,
+    {
+        const POLL_US: u32 = 100;
+        let polls = timeout_ms.saturating_mul(1000 / POLL_US);
+
+        let mut state = self.ensm_get_state();
+        for _ in 0..polls {
+            if state == target {
+                return Ok(());
+            }
+            self.delay.delay_us(POLL_US);
+
+            let next = self.ensm_get_state();
+            if next != state {
+                debug!("AD936x: ENSM {:?} -> {:?}", state, next);
+                state = next;
             }
+        }
+        if state == target {
+            return Ok(());
+        }
+        warn!(
+            "AD936x: timed out waiting for ENSM state {:?}, in {:?}",
+            target, state
+        );
+        Err(Ad9361Error::TimedOut)
+    }
+}
+
+/// Guard returned by [`Ad9361::force_state_scoped`]
+///
+/// Restores the previous ENSM state when dropped. A flush or unknown previous
+/// state cannot be forced, and is not restored
+pub struct EnsmGuard<'g, 'a, SPI, DELAY, RESETB, SYNC> {
+    ad9361: &'g mut Ad9361<'a, SPI, DELAY, RESETB, SYNC>,
+    previous: EnsmState,
+}
+impl<'g, 'a, SPI, DELAY, RESETB, SYNC>
+    EnsmGuard<'g, 'a, SPI, DELAY, RESETB, SYNC>
+{
+    /// ENSM state that will be restored
+    pub fn previous(&self) -> EnsmState {
+        self.previous
+    }
+}
+impl<'g, 'a, SPI, DELAY, RESETB, SYNC> core::ops::Deref
+    for EnsmGuard<'g, 'a, SPI, DELAY, RESETB, SYNC>
+{
+    type Target = Ad9361<'a, SPI, DELAY, RESETB, SYNC>;
+
+    fn deref(&self) -> &Self::Target {
+        self.ad9361
+    }
+}
+impl<'g, 'a, SPI, DELAY, RESETB, SYNC> core::ops::DerefMut
+    for EnsmGuard<'g, 'a, SPI, DELAY, RESETB, SYNC>
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.ad9361
+    }
+}
+impl<'g, 'a, SPI, DELAY, RESETB, SYNC> Drop
+    for EnsmGuard<'g, 'a, SPI, DELAY, RESETB, SYNC>
+{
+    fn drop(&mut self) {
+        // The device may have been removed through the guard
+        if self.ad9361.inner.is_null() {
+            return;
+        }
+        if self.ad9361.ensm_get_state() == self.previous {
+            return;
+        }
+        let forceable = matches!(
+            self.previous,
+            EnsmState::SleepOrWait
+                | EnsmState::Alert
+                | EnsmState::Tx
+                | EnsmState::Rx
+                | EnsmState::Fdd
+        );
+        if forceable && self.ad9361.ensm_force_state(self.previous).is_err() {
+            warn!("AD936x: failed to restore ENSM state");
+        }
+    }
+}
+
+/// Sampling rate methods
+///
+impl<'a, SPI, DELAY, RESETB, SYNC> Ad9361<'a, SPI, DELAY, RESETB, SYNC> {
+    /// Set the RX sampling rate, and return the rate that was achieved
+    ///
+    /// The achieved rate may differ from `hz` due to the granularity of the
+    /// BBPLL and the dividers. The RX and TX sampling rates are derived from
+    /// the same BBPLL, so this also changes the TX sampling rate
+    pub fn set_rx_sampling_freq_checked(
+        &mut self,
+        hz: u32,
+    ) -> Result<u32, Ad9361Error> {
+        self.set_rx_sampling_freq(hz)?;
+        self.get_rx_sampling_freq()
+    }
+    /// Set the TX sampling rate, and return the rate that was achieved
+    ///
+    /// See [`set_rx_sampling_freq_checked`](Self::set_rx_sampling_freq_checked)
+    pub fn set_tx_sampling_freq_checked(
+        &mut self,
+        hz: u32,
+    ) -> Result<u32, Ad9361Error> {
+        self.set_tx_sampling_freq(hz)?;
+        self.get_tx_sampling_freq()
+    }
+    /// Set the RX and TX path clocks, reconfiguring the BBPLL and all the
+    /// dividers
+    ///
+    /// Each array holds the BBPLL, ADC (or DAC), R2 (T2), R1 (T1), CLKRF
+    /// (CLKTF) and sample rate in Hz, in the same form as
+    /// `rx_path_clock_frequencies` and `tx_path_clock_frequencies` of the
+    /// initialisation parameters. Unlike the sampling rate setters, this sets
+    /// the decimation and interpolation of each stage
+    pub fn set_trx_path_clks(
+        &mut self,
+        rx: &[u32; 6],
+        tx: &[u32; 6],
+    ) -> Result<(), Ad9361Error> {
+        assert!(
+            !self.inner.is_null(),
+            "Must call init() method before accessing ad9361"
+        );
+        let (_active, inner_ptr) = self.inner_ptr()?;
+        let mut rx_path_clks = *rx;
+        let mut tx_path_clks = *tx;
+        let status = unsafe {
+            bindings::ad9361_set_trx_path_clks(
+                inner_ptr,
+                rx_path_clks.as_mut_ptr(),
+                tx_path_clks.as_mut_ptr(),
+            )
+        };
+        if status == 0 {
+            self.params.0.rx_path_clock_frequencies = *rx;
+            self.params.0.tx_path_clock_frequencies = *tx;
+            Ok(())
+        } else {
+            Err(status.into())
+        }
+    }
+}
+
+/// BIST methods
+///
+impl<'a, SPI, DELAY, RESETB, SYNC> Ad9361<'a, SPI, DELAY, RESETB, SYNC> {
+    /// Get the BIST tone configuration
+    ///
+    /// The mode, frequency, level and mask are those last passed to
+    /// `bist_tone`, as held by the driver. `enabled` is read from the BIST
+    /// configuration register, and confirms that the generator is running
+    pub fn get_bist_tone(&self) -> Result<BistTone, Ad9361Error> {
+        assert!(
+            !self.inner.is_null(),
+            "Must call init() method before accessing ad9361"
+        );
+        let config = self.read_register(REG_BIST_CONFIG)?;
+        let (_active, inner_ptr) = self.inner_ptr()?;
+        let mut mode = bindings::ad9361_bist_mode::default();
+        let mut frequency = 0;
+        let mut level_db = 0;
+        let mut mask = 0;
+
+        // Returns void: this only copies the tone settings held by the driver
+        unsafe {
+            bindings::ad9361_get_bist_tone(
+                inner_ptr,
+                &mut mode,
+                &mut frequency,
+                &mut level_db,
+                &mut mask,
+            )
+        };
+
+        Ok(BistTone {
+            mode: mode.into(),
+            frequency,
+            level_db,
+            mask,
+            enabled: config & BIST_ENABLE != 0,
+        })
+    }
+    /// Run a PRBS test over the TX → RX digital loopback
+    ///
+    /// The AD9361 does not check PRBS sequences itself; the sequence is
+    /// generated and checked in the baseband processor (usually an FPGA). This
+    /// method disables BIST injection and enables the digital loopback, so
+    /// that the TX data port is returned on the RX data port. It then waits
+    /// for `duration_ms`, and calls `check` to read the result from the
+    /// checker. The previous BIST PRBS and loopback modes are restored
+    /// afterwards, even if `check` reports a failure or setting up the
+    /// loopback fails. Both are attempted, and the first error is returned
+    pub fn run_prbs_loopback_test(
+        &mut self,
+        duration_ms: u32,
+        check: impl FnOnce() -> PrbsResult,
+    ) -> Result<PrbsResult, Ad9361Error>
+    where
+        DELAY: hal::Delay,
+    {
+        let prbs = self.get_bist_prbs();
+        let loopback = self.get_bist_loopback();
+
+        let result = (|| {
+            self.bist_prbs(BistMode::Disable)?;
+            self.bist_loopback(LoopbackMode::Enabled)?;
+            self.delay.delay_ms(duration_ms);
+            Ok(check())
+        })();
+
+        // Attempt both restores, keeping the first error
+        let restored = self.bist_loopback(loopback);
+        let restored = restored.and(self.bist_prbs(prbs));
+        result.and_then(|prbs| restored.map(|()| prbs))
+    }
+}
+
+/// RF bandwidth methods
+///
+impl<'a, SPI, DELAY, RESETB, SYNC> Ad9361<'a, SPI, DELAY, RESETB, SYNC> {
+    /// Set the RX RF bandwidth, and return the bandwidth that was achieved
+    ///
+    /// The analog filters are tuned with a divider of the BBPLL clock, so the
+    /// achieved bandwidth may differ from `hz`. It is calculated from the
+    /// baseband filter tune divider, see
+    /// [`get_rf_bandwidths_actual`](Self::get_rf_bandwidths_actual)
+    pub fn set_rx_rf_bandwidth_checked(
+        &mut self,
+        hz: u32,
+    ) -> Result<u32, Ad9361Error> {
+        self.set_rx_rf_bandwidth(hz)?;
+        self.tuned_rf_bandwidth(false)
+    }
+    /// Set the TX RF bandwidth, and return the bandwidth that was achieved
+    ///
+    /// See [`set_rx_rf_bandwidth_checked`](Self::set_rx_rf_bandwidth_checked)
+    pub fn set_tx_rf_bandwidth_checked(
+        &mut self,
+        hz: u32,
+    ) -> Result<u32, Ad9361Error> {
+        self.set_tx_rf_bandwidth(hz)?;
+        self.tuned_rf_bandwidth(true)
+    }
+    /// RF bandwidth that the RX (`tx` clear) or TX (`tx` set) baseband filter
+    /// is tuned to, from the tune divider and the BBPLL frequency
+    ///
+    /// The driver sets the divider to `ceil(BBPLL / (k * BBBW))`, with
+    /// `k = 1.4 * 2π / ln(2)` for RX and `k = 1.6 * 2π / ln(2)` for TX, and
+    /// the RF bandwidth is twice the baseband bandwidth
+    fn tuned_rf_bandwidth(&self, tx: bool) -> Result<u32, Ad9361Error> {
+        let (divider, msb, k) = if tx {
+            (REG_TX_BBF_TUNE_DIVIDER, REG_TX_BBF_TUNE_MODE, 145_036)
+        } else {
+            (REG_RX_BBF_TUNE_DIVIDE, REG_RX_BBF_TUNE_CONFIG, 126_906)
+        };
+        let div = u64::from(self.read_register(divider)?)
+            | (u64::from(self.read_register(msb)? & BBF_TUNE_DIVIDER_MSB) << 8);
+        if div == 0 {
+            return Err(Ad9361Error::Io);
+        }
+        let (rx_path_clks, _) = self.get_trx_path_clks()?;
+        let bbpll = u64::from(rx_path_clks[0]);
+
+        Ok((2 * bbpll * 10_000 / (div * k)) as u32)
+    }
+}
+
+/// LO frequency methods
+///
+/// The LO frequencies are checked against
+/// [`lo_frequency_range`](Ad9361::lo_frequency_range) before they are passed to
+/// the C driver.
+impl<'a, SPI, DELAY, RESETB, SYNC> Ad9361<'a, SPI, DELAY, RESETB, SYNC> {
+    /// Set the RX LO frequency
+    ///
+    /// Returns `Err(Ad9361Error::InvalidArgument)` if `hz` is outside the
+    /// range supported by the device
+    pub fn set_rx_lo_freq(&mut self, hz: u64) -> Result<(), Ad9361Error> {
+        if !synth::LO_FREQ_RANGE_HZ.contains(&hz) {
+            return Err(Ad9361Error::InvalidArgument);
+        }
+        assert!(
+            !self.inner.is_null(),
+            "Must call init() method before accessing ad9361"
+        );
+        let (_active, inner_ptr) = self.inner_ptr()?;
+
+        let status = unsafe { bindings::ad9361_set_rx_lo_freq(inner_ptr, hz) };
+        if status == 0 {
+            Ok(())
+        } else {
+            Err(status.into())
+        }
+    }
+    /// Set the TX LO frequency
+    ///
+    /// Returns `Err(Ad9361Error::InvalidArgument)` if `hz` is outside the
+    /// range supported by the device
+    pub fn set_tx_lo_freq(&mut self, hz: u64) -> Result<(), Ad9361Error> {
+        if !synth::LO_FREQ_RANGE_HZ.contains(&hz) {
+            return Err(Ad9361Error::InvalidArgument);
+        }
+        assert!(
+            !self.inner.is_null(),
+            "Must call init() method before accessing ad9361"
+        );
+        let (_active, inner_ptr) = self.inner_ptr()?;
+
+        let status = unsafe { bindings::ad9361_set_tx_lo_freq(inner_ptr, hz) };
+        if status == 0 {
+            Ok(())
+        } else {
+            Err(status.into())
+        }
+    }
+
+    /// Switch the RX LO between the internal synthesizer and the external LO
+    /// input
+    pub fn set_rx_lo_int_ext(
+        &mut self,
+        lo: InternalExternalLO,
+    ) -> Result<(), Ad9361Error> {
+        self.set_lo_int_ext(false, lo)
+    }
+    /// Switch the TX LO between the internal synthesizer and the external LO
+    /// input
+    pub fn set_tx_lo_int_ext(
+        &mut self,
+        lo: InternalExternalLO,
+    ) -> Result<(), Ad9361Error> {
+        self.set_lo_int_ext(true, lo)
+    }
+    fn set_lo_int_ext(
+        &mut self,
+        tx: bool,
+        lo: InternalExternalLO,
+    ) -> Result<(), Ad9361Error> {
+        assert!(
+            !self.inner.is_null(),
+            "Must call init() method before accessing ad9361"
+        );
+        let (_active, inner_ptr) = self.inner_ptr()?;
+        let external = lo == InternalExternalLO::External;
+
+        let status = unsafe {
+            if tx {
+                bindings::ad9361_set_tx_lo_int_ext(inner_ptr, lo.into())
+            } else {
+                bindings::ad9361_set_rx_lo_int_ext(inner_ptr, lo.into())
+            }
+        };
+        if status != 0 {
+            return Err(status.into());
+        }
+        if tx {
+            self.params.0.external_tx_lo_enable = external.into();
+        } else {
+            self.params.0.external_rx_lo_enable = external.into();
+        }
+        if !external {
+            self.external_lo_hz[usize::from(tx)] = None;
+        }
+        Ok(())
+    }
+    /// Select the external LO input for the RX LO, and set its frequency
+    ///
+    /// `hz` is the RX LO frequency. The external source must supply twice
+    /// this frequency, which the AD9361 divides by two. The synthesizer is
+    /// bypassed, so the frequency is not read back from the driver. Instead
+    /// it is recorded here, and used in place of the synthesizer frequency by
+    /// methods that depend on the RX LO, such as
+    /// [`load_gain_table`](Self::load_gain_table) and the DC offset settings.
+    /// When building a gain table with `GainTable::new_from_recommended`,
+    /// pass the same frequency so the table for the right band is chosen.
+    ///
+    /// Returns `Err(Ad9361Error::InvalidArgument)` if `hz` is outside
+    /// [`lo_frequency_range`](Ad9361::lo_frequency_range)
+    pub fn set_rx_external_lo_freq(
+        &mut self,
+        hz: u64,
+    ) -> Result<(), Ad9361Error> {
+        self.set_external_lo_freq(false, hz)
+    }
+    /// Select the external LO input for the TX LO, and set its frequency
+    ///
+    /// See [`set_rx_external_lo_freq`](Self::set_rx_external_lo_freq)
+    pub fn set_tx_external_lo_freq(
+        &mut self,
+        hz: u64,
+    ) -> Result<(), Ad9361Error> {
+        self.set_external_lo_freq(true, hz)
+    }
+    fn set_external_lo_freq(
+        &mut self,
+        tx: bool,
+        hz: u64,
+    ) -> Result<(), Ad9361Error> {
+        if !synth::LO_FREQ_RANGE_HZ.contains(&hz) {
+            return Err(Ad9361Error::InvalidArgument);
+        }
+        self.set_lo_int_ext(tx, InternalExternalLO::External)?;
+        self.external_lo_hz[usize::from(tx)] = Some(hz);
+        if tx {
+            self.params.0.tx_synthesizer_frequency_hz = hz;
+        } else {
+            self.params.0.rx_synthesizer_frequency_hz = hz;
+        }
+        Ok(())
+    }
+    /// Current RX LO frequency, from the external LO if it is selected
+    fn rx_lo_hz(&self) -> Result<u64, Ad9361Error> {
+        match self.external_lo_hz[0] {
+            Some(hz) => Ok(hz),
+            None => self.get_rx_lo_freq(),
+        }
+    }
+}
+impl Ad9361<'static, (), (), (), ()> {
+    /// Range of RX and TX LO frequencies in Hz supported by the device
+    ///
+    /// This is 70 MHz to 6 GHz, or 325 MHz to 3.8 GHz with the
+    /// `ad9363a_device` feature. Call as `Ad9361::lo_frequency_range()`
+    pub fn lo_frequency_range() -> RangeInclusive<u64> {
+        synth::LO_FREQ_RANGE_HZ
+    }
+    /// Number of `u32` words of heap required by [`init`](Ad9361::init),
+    /// equal to [`MIN_HEAP_WORDS`]
+    #[deprecated(note = "use MIN_HEAP_WORDS")]
+    pub const REQUIRED_HEAP_WORDS: usize = MIN_HEAP_WORDS;
+}
+
+/// Compile-time check that a heap array is large enough
+struct HeapSize<const N: usize>;
+impl<const N: usize> HeapSize<N> {
+    const CHECK: () =
+        assert!(N >= MIN_HEAP_WORDS, "heap is smaller than MIN_HEAP_WORDS");
+}
+
+/// Temperature sensor methods
+///
+impl<'a, SPI, DELAY, RESETB, SYNC> Ad9361<'a, SPI, DELAY, RESETB, SYNC> {
+    /// Time allowed for a one-shot temperature measurement, in microseconds
+    const TEMP_SENSE_ONE_SHOT_US: u32 = 1000;
+
+    /// Get the temperature in millidegrees Celsius
+    ///
+    /// With periodic measurement enabled, this is the result of the most
+    /// recent measurement. Otherwise a single measurement is started, and
+    /// this blocks for 1 ms while it completes, see
+    /// [`set_temp_sense_periodic`](Self::set_temp_sense_periodic)
+    pub fn get_temperature_millicelsius(&self) -> Result<i32, Ad9361Error> {
+        assert!(
+            !self.inner.is_null(),
+            "Must call init() method before accessing ad9361"
+        );
+        let (_active, inner_ptr) = self.inner_ptr()?;
+        let periodic = unsafe {
+            (*(*inner_ptr).pdata).auxadc_ctrl.periodic_temp_measuremnt
+        };
+        if !periodic {
+            self.write_register(REG_START_TEMP_READING, START_TEMP_READING)?;
+            interop::udelay(Self::TEMP_SENSE_ONE_SHOT_US);
+            self.write_register(REG_START_TEMP_READING, 0)?;
+        }
+
+        let (_active, inner_ptr) = self.inner_ptr()?;
+        let mut millicelsius = 0;
+        let status = unsafe {
+            bindings::ad9361_get_temperature(inner_ptr, &mut millicelsius)
+        };
+        if status == 0 {
+            Ok(millicelsius)
+        } else {
+            Err(status.into())
+        }
+    }
+    /// Get the temperature in degrees Celsius
+    ///
+    /// See [`get_temperature_millicelsius`](Self::get_temperature_millicelsius)
+    /// to avoid floating point
+    pub fn get_temperature(&self) -> Result<f32, Ad9361Error> {
+        Ok(self.get_temperature_millicelsius()? as f32 / 1000.)
+    }
+    /// Set the offset added to the temperature sensor reading
+    ///
+    /// The offset is in temperature sensor codes of about 0.88 ºC, and
+    /// calibrates the sensor for a particular device. The initial value is
+    /// `temp_sense_offset_signed` from the initialisation parameters
+    pub fn set_temp_sense_offset(
+        &mut self,
+        offset: i8,
+    ) -> Result<(), Ad9361Error> {
+        self.write_register(REG_TEMP_OFFSET, offset as u8)?;
+
+        let (_active, inner_ptr) = self.inner_ptr()?;
+        unsafe {
+            (*(*inner_ptr).pdata).auxadc_ctrl.offset = offset;
+        }
+        self.params.0.temp_sense_offset_signed = offset;
+        Ok(())
+    }
+    /// Enable or disable periodic temperature measurement, and set the
+    /// interval between measurements
+    ///
+    /// The interval is counted in BBPLL cycles, so it is rounded to the
+    /// nearest step of 2<sup>29</sup> BBPLL cycles (about 0.55 s at the
+    /// default BBPLL frequency). It should be set again after the BBPLL
+    /// frequency is changed. When periodic measurement is disabled,
+    /// [`get_temperature_millicelsius`](Self::get_temperature_millicelsius)
+    /// starts a single measurement and waits for it, rather than returning a
+    /// stale value.
+    ///
+    /// Returns `Err(Ad9361Error::InvalidArgument)` if the interval is too long
+    /// for the current BBPLL frequency, which is only possible above about
+    /// 1 GHz
+    pub fn set_temp_sense_periodic(
+        &mut self,
+        enable: bool,
+        interval_ms: u16,
+    ) -> Result<(), Ad9361Error> {
+        let (rx_path_clks, _) = self.get_trx_path_clks()?;
+        let bbpll_khz = u64::from(rx_path_clks[0] / 1000);
+        let steps = (u64::from(interval_ms) * bbpll_khz + (1 << 28)) >> 29;
+        if steps > MEASUREMENT_TIME_INTERVAL_MAX {
+            return Err(Ad9361Error::InvalidArgument);
+        }
+
+        let enable_bit = if enable {
+            TEMP_SENSE_PERIODIC_ENABLE
+        } else {
+            0
+        };
+        self.write_register(
+            REG_TEMP_SENSE2,
+            ((steps as u8) << MEASUREMENT_TIME_INTERVAL_SHIFT) | enable_bit,
+        )?;
+
+        let (_active, inner_ptr) = self.inner_ptr()?;
+        unsafe {
+            let auxadc_ctrl = &mut (*(*inner_ptr).pdata).auxadc_ctrl;
+            auxadc_ctrl.periodic_temp_measuremnt = enable;
+            auxadc_ctrl.temp_time_inteval_ms = interval_ms.into();
+        }
+        self.params.0.temp_sense_periodic_measurement_enable = enable.into();
+        self.params.0.temp_sense_measurement_interval_ms = interval_ms;
+        Ok(())
+    }
+}
+
+/// Bias trim methods
+///
+/// These are advanced settings for manufacturing calibration. The driver
+/// sets both trims to 0x0E during initialisation, and the device is only
+/// characterised with those values. An incorrect trim shifts the bias
+/// currents of the whole device, including the BBPLL and RF synthesizers, so
+/// the synthesizers should be recalibrated after a change.
+impl<'a, SPI, DELAY, RESETB, SYNC> Ad9361<'a, SPI, DELAY, RESETB, SYNC> {
+    /// Set the master bias trim, 0 to 31
+    ///
+    /// Returns `Err(Ad9361Error::InvalidArgument)` if `trim` is greater than
+    /// 31
+    pub fn set_master_bias_trim(
+        &mut self,
+        trim: u8,
+    ) -> Result<(), Ad9361Error> {
+        if trim > MASTER_BIAS_TRIM_MASK {
+            return Err(Ad9361Error::InvalidArgument);
+        }
+        let config = self.read_register(REG_BANDGAP_CONFIG0)?;
+        self.write_register(
+            REG_BANDGAP_CONFIG0,
+            (config & !MASTER_BIAS_TRIM_MASK) | trim,
+        )
+    }
+    /// Get the master bias trim
+    pub fn get_master_bias_trim(&self) -> Result<u8, Ad9361Error> {
+        Ok(self.read_register(REG_BANDGAP_CONFIG0)? & MASTER_BIAS_TRIM_MASK)
+    }
+    /// Set the bandgap temperature coefficient trim, 0 to 31
+    ///
+    /// Returns `Err(Ad9361Error::InvalidArgument)` if `trim` is greater than
+    /// 31
+    pub fn set_bandgap_temp_trim(
+        &mut self,
+        trim: u8,
+    ) -> Result<(), Ad9361Error> {
+        if trim > BANDGAP_TEMP_TRIM_MASK {
+            return Err(Ad9361Error::InvalidArgument);
+        }
+        let config = self.read_register(REG_BANDGAP_CONFIG1)?;
+        self.write_register(
+            REG_BANDGAP_CONFIG1,
+            (config & !BANDGAP_TEMP_TRIM_MASK) | trim,
+        )
+    }
+    /// Get the bandgap temperature coefficient trim
+    pub fn get_bandgap_temp_trim(&self) -> Result<u8, Ad9361Error> {
+        Ok(self.read_register(REG_BANDGAP_CONFIG1)? & BANDGAP_TEMP_TRIM_MASK)
+    }
+}
+
+/// Profile methods
+///
+impl<'a, SPI, DELAY, RESETB, SYNC> Ad9361<'a, SPI, DELAY, RESETB, SYNC> {
+    /// Capture the current radio settings in a profile, which can later be
+    /// restored with [`apply_profile`](Self::apply_profile)
+    ///
+    /// The TX attenuation of a channel muted with [`tx_mute`](Self::tx_mute)
+    /// is captured as the muted attenuation
+    pub fn capture_profile(&self) -> Result<Ad9361Profile, Ad9361Error> {
+        Ok(Ad9361Profile {
+            rx_lo_hz: self.rx_lo_hz()?,
+            tx_lo_hz: match self.external_lo_hz[1] {
+                Some(hz) => hz,
+                None => self.get_tx_lo_freq()?,
+            },
+            sampling_hz: self.get_rx_sampling_freq()?,
+            rx_bandwidth_hz: self.get_rx_rf_bandwidth()?,
+            tx_bandwidth_hz: self.get_tx_rf_bandwidth()?,
+            gain_control: [
+                self.get_rx_gain_control_mode(RxChannel::Rx1)?,
+                self.get_rx_gain_control_mode(RxChannel::Rx2)?,
+            ],
+            tx_attenuation_mdb: [
+                self.get_tx_attenuation(TxChannel::Tx1)?,
+                self.get_tx_attenuation(TxChannel::Tx2)?,
+            ],
+            rx_fir_enabled: self.get_rx_fir_en_dis()?,
+            tx_fir_enabled: self.get_tx_fir_en_dis()?,
+        })
+    }
+    /// Apply the radio settings of a profile
+    ///
+    /// The sampling rate is set first, followed by the FIR filter enables,
+    /// RF bandwidths, LO frequencies, gain control modes and TX attenuations.
+    /// If the RX or TX LO is external, its frequency is recorded as with
+    /// [`set_rx_external_lo_freq`](Self::set_rx_external_lo_freq). Stops at
+    /// the first setting that fails, leaving the following settings
+    /// unchanged
+    pub fn apply_profile(
+        &mut self,
+        profile: &Ad9361Profile,
+    ) -> Result<(), Ad9361Error> {
+        self.set_rx_sampling_freq(profile.sampling_hz)?;
+        self.set_rx_fir_en_dis(profile.rx_fir_enabled)?;
+        self.set_tx_fir_en_dis(profile.tx_fir_enabled)?;
+        self.set_rx_rf_bandwidth(profile.rx_bandwidth_hz)?;
+        self.set_tx_rf_bandwidth(profile.tx_bandwidth_hz)?;
+        if self.external_lo_hz[0].is_some() {
+            self.set_rx_external_lo_freq(profile.rx_lo_hz)?;
+        } else {
+            self.set_rx_lo_freq(profile.rx_lo_hz)?;
+        }
+        if self.external_lo_hz[1].is_some() {
+            self.set_tx_external_lo_freq(profile.tx_lo_hz)?;
+        } else {
+            self.set_tx_lo_freq(profile.tx_lo_hz)?;
+        }
+        self.set_rx_gain_control_mode(RxChannel::Rx1, profile.gain_control[0])?;
+        self.set_rx_gain_control_mode(RxChannel::Rx2, profile.gain_control[1])?;
+        self.set_tx_attenuation(TxChannel::Tx1, profile.tx_attenuation_mdb[0])?;
+        self.set_tx_attenuation(TxChannel::Tx2, profile.tx_attenuation_mdb[1])
+    }
+}
+
+/// Status methods
+///
+impl<'a, SPI, DELAY, RESETB, SYNC> Ad9361<'a, SPI, DELAY, RESETB, SYNC> {
+    /// Read the product ID from the product ID register (0x037)
+    ///
+    /// See [`ProductId`]: the supported devices share one product ID
+    pub fn product_id(&self) -> Result<ProductId, Ad9361Error> {
+        let id = self.read_register(REG_PRODUCT_ID)? & PRODUCT_ID_MASK;
+        Ok(match id {
+            PRODUCT_ID_9361 => ProductId::Ad936x,
+            _ => ProductId::Unknown(id),
+        })
+    }
+    /// Read the silicon revision from the product ID register (0x037)
+    pub fn device_revision(&self) -> Result<u8, Ad9361Error> {
+        Ok(self.read_register(REG_PRODUCT_ID)? & REV_MASK)
+    }
+    /// Read the fault flags, and clear the latched faults
+    ///
+    /// The channel overflow flags are latched, and are cleared by this method.
+    /// The PLL lock flags report the live lock status. See [`FaultFlags`]
+    pub fn read_and_clear_faults(&mut self) -> Result<FaultFlags, Ad9361Error> {
+        let mut faults = FaultFlags::empty();
+
+        let ch1 = self.read_register(REG_CH_1_OVERFLOW)?;
+        let ch2 = self.read_register(REG_CH_2_OVERFLOW)?;
+        let rx_synth = self.read_register(REG_RX_CP_OVERRANGE_VCO_LOCK)?;
+        let tx_synth = self.read_register(REG_TX_CP_OVERRANGE_VCO_LOCK)?;
+
+        if ch1 & BBPLL_LOCK == 0 {
+            faults |= FaultFlags::BBPLL_UNLOCKED;
+        }
+        if rx_synth & VCO_LOCK == 0 {
+            faults |= FaultFlags::RX_SYNTH_UNLOCKED;
+        }
+        if tx_synth & VCO_LOCK == 0 {
+            faults |= FaultFlags::TX_SYNTH_UNLOCKED;
+        }
+        if ch1 & OVERFLOW_MASK != 0 {
+            faults |= FaultFlags::CH1_OVERFLOW;
+            // write one to clear
+            self.write_register(REG_CH_1_OVERFLOW, ch1 & OVERFLOW_MASK)?;
+        }
+        if ch2 & OVERFLOW_MASK != 0 {
+            faults |= FaultFlags::CH2_OVERFLOW;
+            // write one to clear
+            self.write_register(REG_CH_2_OVERFLOW, ch2 & OVERFLOW_MASK)?;
+        }
+
+        Ok(faults)
+    }
+    /// Range of plausible die temperatures, in degrees Celsius
+    const SELF_TEST_TEMPERATURE: core::ops::RangeInclusive<f32> = -40.0..=125.0;
+    /// Check the major subsystems, and report the result of each check
+    ///
+    /// The checks are: the product ID, SPI readback of test patterns written
+    /// to the gain table address register (which is restored afterwards), the
+    /// BBPLL and RF synthesizer lock status, and the die temperature being in
+    /// the range -40°C to 125°C. The latched fault flags are not cleared.
+    ///
+    /// The BIST modes generate test data, but the PRBS and tone checks are
+    /// made by the baseband processor, so they are not included here. `Err` is
+    /// only returned if a SPI access fails
+    pub fn self_test(&mut self) -> Result<SelfTestReport, Ad9361Error> {
+        let product_id = self.read_register(REG_PRODUCT_ID)?;
+
+        let address = self.read_register(REG_GAIN_TABLE_ADDRESS)?;
+        let mut spi_readback = true;
+        for pattern in [0x55, 0x2A] {
+            self.write_register(REG_GAIN_TABLE_ADDRESS, pattern)?;
+            spi_readback &=
+                self.read_register(REG_GAIN_TABLE_ADDRESS)? == pattern;
+        }
+        self.write_register(REG_GAIN_TABLE_ADDRESS, address)?;
+
+        let ch1 = self.read_register(REG_CH_1_OVERFLOW)?;
+        let rx_synth = self.read_register(REG_RX_CP_OVERRANGE_VCO_LOCK)?;
+        let tx_synth = self.read_register(REG_TX_CP_OVERRANGE_VCO_LOCK)?;
+        let temperature = self.get_temperature()?;
+
+        Ok(SelfTestReport {
+            product_id: product_id & PRODUCT_ID_MASK == PRODUCT_ID_9361,
+            spi_readback,
+            bbpll_locked: ch1 & BBPLL_LOCK != 0,
+            rx_synth_locked: rx_synth & VCO_LOCK != 0,
+            tx_synth_locked: tx_synth & VCO_LOCK != 0,
+            temperature,
+            temperature_plausible: Self::SELF_TEST_TEMPERATURE
+                .contains(&temperature),
+        })
+    }
+    /// Read the RX and TX path clocks from the driver
+    fn get_trx_path_clks(&self) -> Result<([u32; 6], [u32; 6]), Ad9361Error> {
+        assert!(
+            !self.inner.is_null(),
+            "Must call init() method before accessing ad9361"
+        );
+        let (_active, inner_ptr) = self.inner_ptr()?;
+        let mut rx_path_clks = [0u32; 6];
+        let mut tx_path_clks = [0u32; 6];
+        let status = unsafe {
+            bindings::ad9361_get_trx_path_clks(
+                inner_ptr,
+                rx_path_clks.as_mut_ptr(),
+                tx_path_clks.as_mut_ptr(),
+            )
+        };
+        if status == 0 {
+            Ok((rx_path_clks, tx_path_clks))
+        } else {
+            Err(status.into())
+        }
+    }
+    /// Get the frequencies of the clock tree, as realised by the dividers
+    ///
+    /// These may differ from the requested sampling rate, due to the
+    /// granularity of the BBPLL and the dividers
+    pub fn get_clock_frequencies(
+        &self,
+    ) -> Result<ClockFrequencies, Ad9361Error> {
+        let (rx, tx) = self.get_trx_path_clks()?;
+
+        Ok(ClockFrequencies {
+            bbpll: rx[0],
+            adc: rx[1],
+            r2: rx[2],
+            r1: rx[3],
+            clkrf: rx[4],
+            rx_sample: rx[5],
+            dac: tx[1],
+            t2: tx[2],
+            t1: tx[3],
+            clktf: tx[4],
+            tx_sample: tx[5],
+        })
+    }
+    /// Get a summary of the current operating mode
+    ///
+    /// The duplex mode and number of channels are read from the ENSM mode and
+    /// RX channel enable registers, and the gain control modes from the driver
+    pub fn operating_mode(&self) -> Result<OperatingMode, Ad9361Error> {
+        let ensm_mode = self.read_register(REG_ENSM_MODE)?;
+        let rx_enable = self.read_register(REG_RX_ENABLE_FILTER_CTRL)?;
+
+        let duplex = if ensm_mode & FDD_MODE != 0 {
+            DuplexMode::Fdd
+        } else {
+            DuplexMode::Tdd
+        };
+        let channels =
+            if rx_enable & RX_CHANNEL_ENABLE_MASK == RX_CHANNEL_ENABLE_MASK {
+                ChannelMode::TwoByTwo
+            } else {
+                ChannelMode::OneByOne
+            };
+        let gain_control = [
+            self.get_rx_gain_control_mode(0)?,
+            self.get_rx_gain_control_mode(1)?,
+        ];
+
+        Ok(OperatingMode {
+            duplex,
+            channels,
+            gain_control,
+        })
+    }
+    /// Get the RX and TX RF bandwidths, as (`rx_bw`, `tx_bw`) in Hz
+    ///
+    /// These are the bandwidths that the baseband filters are tuned to,
+    /// calculated from their tune dividers and the BBPLL frequency. The
+    /// [`get_rx_rf_bandwidth`](Self::get_rx_rf_bandwidth) and
+    /// [`get_tx_rf_bandwidth`](Self::get_tx_rf_bandwidth) getters instead
+    /// return the requested bandwidths, after clamping by the driver
+    pub fn get_rf_bandwidths_actual(&self) -> Result<(u32, u32), Ad9361Error> {
+        let rx_bw = self.tuned_rf_bandwidth(false)?;
+        let tx_bw = self.tuned_rf_bandwidth(true)?;
+
+        Ok((rx_bw, tx_bw))
+    }
+}
+
+/// Transmit attenuation applied while a channel is muted, the maximum
+const TX_MUTE_ATTENUATION_MDB: u32 = 89_750;
+
+/// Transmit attenuation methods
+///
+/// The AD9361 does not have a hardware ramp for changes in transmit
+/// attenuation; each new attenuation word is applied in a single step. To make
+/// glitch-free power changes, either step the attenuation in software with
+/// [`set_tx_attenuation`](Self::set_tx_attenuation), or defer the update to the
+/// next time the ENSM passes through the ALERT state with
+/// [`set_tx_atten_update_in_alert`](Self::set_tx_atten_update_in_alert). The
+/// latter synchronises the change to TDD bursts without involving the MCU.
+impl<'a, SPI, DELAY, RESETB, SYNC> Ad9361<'a, SPI, DELAY, RESETB, SYNC> {
+    /// Set whether changes to the transmit attenuation are deferred until the
+    /// ENSM enters the ALERT state (true), or applied immediately (false)
+    pub fn set_tx_atten_update_in_alert(
+        &mut self,
+        enable: bool,
+    ) -> Result<(), Ad9361Error> {
+        let value = self.read_register(REG_TX2_DIG_ATTEN)?;
+        let value = if enable {
+            value & !IMMEDIATELY_UPDATE_TPC_ATTEN
+        } else {
+            value | IMMEDIATELY_UPDATE_TPC_ATTEN
+        };
+        self.write_register(REG_TX2_DIG_ATTEN, value)?;
+
+        // The driver rewrites the register on each attenuation change
+        let (_active, inner_ptr) = self.inner_ptr()?;
+        unsafe {
+            (*(*inner_ptr).pdata).update_tx_gain_via_alert = enable;
+        }
+        self.params.0.update_tx_gain_in_alert_enable = enable as u8;
+        Ok(())
+    }
+    /// Get whether changes to the transmit attenuation are deferred until the
+    /// ENSM enters the ALERT state (true), or applied immediately (false)
+    pub fn get_tx_atten_update_in_alert(&self) -> Result<bool, Ad9361Error> {
+        let value = self.read_register(REG_TX2_DIG_ATTEN)?;
+        Ok(value & IMMEDIATELY_UPDATE_TPC_ATTEN == 0)
+    }
+    /// Get the transmit attenuation of `channel` in dB
+    ///
+    /// See [`get_tx_attenuation`](Self::get_tx_attenuation) to avoid floating
+    /// point
+    pub fn get_tx_attenuation_db(
+        &self,
+        channel: impl Into<u8>,
+    ) -> Result<f32, Ad9361Error> {
+        Ok(self.get_tx_attenuation(channel)? as f32 / 1000.)
+    }
+    /// Set the transmit attenuation (in mdB) of TX1 if `tx1` is set and TX2
+    /// if `tx2` is set, in a single update
+    ///
+    /// Both channels are written before the new attenuation is applied, so
+    /// there is no transient mismatch between the channels
+    pub fn set_tx_attenuation_both(
+        &mut self,
+        atten_mdb: u32,
+        tx1: bool,
+        tx2: bool,
+    ) -> Result<(), Ad9361Error> {
+        assert!(
+            !self.inner.is_null(),
+            "Must call init() method before accessing ad9361"
+        );
+        let (_active, inner_ptr) = self.inner_ptr()?;
+
+        let status = unsafe {
+            let immed = !(*(*inner_ptr).pdata).update_tx_gain_via_alert;
+            bindings::ad9361_set_tx_atten(inner_ptr, atten_mdb, tx1, tx2, immed)
+        };
+        if status == 0 {
+            Ok(())
+        } else {
+            Err(status.into())
+        }
+    }
+    /// Mute (true) or unmute (false) the selected transmit channel
+    ///
+    /// Muting saves the current attenuation of the channel and sets the
+    /// maximum attenuation. Unmuting restores the saved attenuation exactly.
+    /// Muting an already muted channel, or unmuting a channel that is not
+    /// muted, leaves the attenuation unchanged.
+    ///
+    /// Returns the attenuation (in mdB) of the channel before this call
+    pub fn tx_mute(
+        &mut self,
+        channel: TxChannel,
+        mute: bool,
+    ) -> Result<u32, Ad9361Error> {
+        let previous = self.get_tx_attenuation(channel)?;
+        let index = usize::from(u8::from(channel));
+
+        match (mute, self.tx_mute_attenuation[index]) {
+            (true, None) => {
+                self.set_tx_attenuation(channel, TX_MUTE_ATTENUATION_MDB)?;
+                self.tx_mute_attenuation[index] = Some(previous);
+            }
+            (false, Some(attenuation)) => {
+                self.set_tx_attenuation(channel, attenuation)?;
+                self.tx_mute_attenuation[index] = None;
+            }
+            _ => {}
+        }
+        Ok(previous)
+    }
+    /// Is the selected transmit channel muted by [`tx_mute`](Self::tx_mute)?
+    pub fn is_tx_muted(&self, channel: TxChannel) -> bool {
+        self.tx_mute_attenuation[usize::from(u8::from(channel))].is_some()
+    }
+    /// Get the TX monitor measurement for the selected channel.
+    /// Channel 0 = TX1, 1 = TX2
+    ///
+    /// The TX monitor of the channel must be enabled, with the receive port
+    /// `TX_MON1`, `TX_MON2` or `TX_MON1_2`. The registers are read directly,
+    /// as in `ad9361_get_tx_rssi`
+    pub fn get_tx_monitor(
+        &self,
+        channel: impl Into<u8>,
+    ) -> Result<TxMonitor, Ad9361Error> {
+        let channel = channel.into();
+        if channel > 1 {
+            return Err(Ad9361Error::InvalidArgument);
+        }
+        assert!(
+            !self.inner.is_null(),
+            "Must call init() method before accessing ad9361"
+        );
+        let (_active, inner_ptr) = self.inner_ptr()?;
+        // TX RSSI LSB, TX RSSI2 and TX RSSI1 in a single transfer
+        let mut values = [0u8; 3];
+        let status = unsafe {
+            bindings::ad9361_spi_readm(
+                (*inner_ptr).spi,
+                REG_TX_RSSI_LSB.into(),
+                values.as_mut_ptr(),
+                values.len() as u32,
+            )
+        };
+        if status < 0 {
+            return Err(status.into());
+        }
+        let value = |reg: u16| values[usize::from(REG_TX_RSSI_LSB - reg)];
+        let lsb = value(REG_TX_RSSI_LSB);
+        let rssi = if channel == 0 {
+            (u16::from(value(REG_TX_RSSI1)) << 1) | u16::from(lsb & TX_RSSI_1)
+        } else {
+            (u16::from(value(REG_TX_RSSI2)) << 1)
+                | u16::from((lsb & TX_RSSI_2) >> 1)
+        };
+        Ok(TxMonitor { rssi })
+    }
+}
+
+/// Multi-chip synchronisation methods
+///
+impl<'a, SPI, DELAY, RESETB, SYNC> Ad9361<'a, SPI, DELAY, RESETB, SYNC> {
+    /// Perform one step of the multi-chip synchronisation (MCS) sequence.
+    /// Steps are numbered 1 to 5
+    ///
+    /// To synchronise several devices, each step must be performed on every
+    /// device before moving to the next step, following `ad9361_do_mcs`. The
+    /// devices are held in the Alert state, and each step is given 100 ms to
+    /// complete:
+    ///
+    /// ```text
+    /// for step in 1..=5 {
+    ///     slave.mcs(step)?;
+    ///     master.mcs(step)?;
+    ///     delay.delay_ms(100);
+    /// }
+    /// ```
+    ///
+    /// SYNC_IN must be shared by all the devices. Steps 2 and 4 pulse SYNC_IN
+    /// from any device that has a `sync` pin, and do nothing on a device
+    /// without one, so the pin should be passed to [`new`](Self::new) for
+    /// the master only, which performs each step last.
+    ///
+    /// All the devices may be driven from the same thread, with each `Ad9361`
+    /// owning its own heap.
+    ///
+    /// SYNC_IN is sampled on the rising edge of the reference clock. The pulse
+    /// must be high for at least one reference clock period, and its edges
+    /// must meet the setup and hold times relative to the reference clock at
+    /// every device. When synchronising multiple devices, SYNC_IN should be
+    /// distributed with matched delays.
+    pub fn mcs(&mut self, step: u8) -> Result<(), Ad9361Error> {
+        if !(1..=5).contains(&step) {
+            return Err(Ad9361Error::InvalidArgument);
+        }
+        assert!(
+            !self.inner.is_null(),
+            "Must call init() method before accessing ad9361"
+        );
+        let (_active, inner_ptr) = self.inner_ptr()?;
+        let status = unsafe { bindings::ad9361_mcs(inner_ptr, step.into()) };
+        if status == 0 {
+            Ok(())
+        } else {
+            Err(status.into())
+        }
+    }
+}
+
+/// Implementation of some methods from ad9361_conv.c
+///
+impl<'a, SPI, DELAY, RESETB, SYNC> Ad9361<'a, SPI, DELAY, RESETB, SYNC> {
+    /// Set interface timing. Set `tx` for the TX path, clear `tx` for the RX
+    /// path. If the `clock_delay` value has changed since the previous call or
+    /// initial configuration, set `clock_changed`. The ENSM is then forced to
+    /// Alert while the delays are changed, and returned to its previous state.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `clock_delay` or `data_delay` are >= 16
+    pub fn set_intf_delay(
+        &mut self,
+        tx: bool,
+        clock_delay: u32,
+        data_delay: u32,
+        clock_changed: bool,
+    ) -> Result<(), Ad9361Error> {
+        assert!(clock_delay < 16);
+        assert!(data_delay < 16);
+
+        assert!(
+            !self.inner.is_null(),
+            "Must call init() method before accessing ad9361"
+        );
+        let previous = self.ensm_get_state();
+        let (_active, inner_ptr) = self.inner_ptr()?;
+        let status = unsafe {
+            if clock_changed {
+                let alert = EnsmState::Alert as u8;
+                bindings::ad9361_ensm_force_state(inner_ptr, alert);
+            }
+            let address = if tx { 0x7 } else { 0x6 };
+            let value = (clock_delay << 4) | data_delay;
+            let status =
+                bindings::ad9361_spi_write((*inner_ptr).spi, address, value);
+            if clock_changed
+                && previous != EnsmState::Alert
+                && previous != EnsmState::Unknown
+            {
+                bindings::ad9361_ensm_force_state(inner_ptr, previous.into());
+            }
+            status
+        };
+        if status == 0 {
+            Ok(())
+        } else {
+            Err(status.into())
+        }
+    }
+
+    /// Set the LVDS bias control register 0x03C
+    ///
+    /// # Panics
+    ///
+    /// Panics if `lvds_bias_m_v` is < 75 or > 450
+    pub fn set_lvds_bias_control(
+        &mut self,
+        rx_on_chip_term: bool,
+        lvds_tx_lo_vcm: bool,
+        lvds_bias_m_v: u32,
+    ) -> Result<(), Ad9361Error> {
+        assert!(lvds_bias_m_v <= 450);
+        assert!(lvds_bias_m_v >= 75);
+
+        let address = 0x03C;
+        let value = if rx_on_chip_term { 0x20 } else { 0 }
+            | if lvds_tx_lo_vcm { 0x08 } else { 0 }
+            | ((lvds_bias_m_v - 75) / 75);
+
+        assert!(
+            !self.inner.is_null(),
+            "Must call init() method before accessing ad9361"
+        );
+        let (_active, inner_ptr) = self.inner_ptr()?;
+        let status = unsafe {
+            bindings::ad9361_spi_write((*inner_ptr).spi, address, value)
+        };
+        if status == 0 {
+            Ok(())
+        } else {
+            Err(status.into())
+        }
+    }
+
+    /// Switch the data interface between LVDS (`lvds` set) and CMOS (`lvds`
+    /// clear) modes
+    ///
+    /// The ENSM is forced to Alert while the interface is reconfigured, and
+    /// then returned to its previous state. The clock and data delays from the
+    /// initialisation parameters are re-applied. In LVDS mode the LVDS bias and
+    /// on-chip termination from the initialisation parameters are re-applied;
+    /// in CMOS mode the on-chip termination is disabled.
+    ///
+    /// This is a significant reconfiguration of the data interface. The FPGA
+    /// or baseband processor must be reconfigured to match, and any interface
+    /// timing calibration repeated.
+    pub fn set_interface_electrical_mode(
+        &mut self,
+        lvds: bool,
+    ) -> Result<(), Ad9361Error> {
+        assert!(
+            !self.inner.is_null(),
+            "Must call init() method before accessing ad9361"
+        );
+        let (_active, inner_ptr) = self.inner_ptr()?;
+        let previous = self.ensm_get_state();
+        unsafe {
+            let alert = EnsmState::Alert as u8;
+            bindings::ad9361_ensm_force_state(inner_ptr, alert);
+        }
+
+        let result = (|| {
+            let conf = self.read_register(REG_PARALLEL_PORT_CONF_3)?;
+            let conf = if lvds {
+                conf | LVDS_MODE
+            } else {
+                conf & !LVDS_MODE
+            };
+            self.write_register(REG_PARALLEL_PORT_CONF_3, conf)?;
+
+            let params = &self.params.0;
+            let rx_delay =
+                (params.rx_data_clock_delay << 4) | params.rx_data_delay;
+            let tx_delay =
+                (params.tx_fb_clock_delay << 4) | params.tx_data_delay;
+            self.write_register(REG_RX_CLOCK_DATA_DELAY, rx_delay as u8)?;
+            self.write_register(REG_TX_CLOCK_DATA_DELAY, tx_delay as u8)?;
+
+            let bias = ((params.lvds_bias_mV.clamp(75, 450) - 75) / 75) as u8;
+            let bias = if lvds && params.lvds_rx_onchip_termination_enable != 0
+            {
+                bias | RX_ON_CHIP_TERM
+            } else {
+                bias
+            };
+            self.write_register(REG_LVDS_BIAS_CTRL, bias)
+        })();
+
+        if previous != EnsmState::Alert && previous != EnsmState::Unknown {
+            unsafe {
+                bindings::ad9361_ensm_force_state(inner_ptr, previous.into());
+            }
+        }
+        if result.is_ok() {
+            self.params.0.lvds_mode_enable = lvds.into();
+        }
+        result
+    }
+    /// Returns true if the data interface is in LVDS mode, false if it is in
+    /// CMOS mode
+    pub fn get_interface_electrical_mode(&self) -> Result<bool, Ad9361Error> {
+        let conf = self.read_register(REG_PARALLEL_PORT_CONF_3)?;
+        Ok(conf & LVDS_MODE != 0)
+    }
+    /// Set the configuration of the parallel data port, without a full
+    /// re-initialisation
+    ///
+    /// The ENSM is forced to Alert while the parallel port configuration
+    /// registers are written, so that no data is transferred while the port
+    /// changes, and then returned to its previous state. Every field requires
+    /// this ALERT transition. The fields that change the width or rate of the
+    /// port (`swap_ports`, `single_data_rate`, `half_duplex`, `single_port`,
+    /// `full_port` and `fdd_rx_rate_2tx`) also change the timing of the
+    /// interface. The FPGA or baseband processor must be reconfigured to
+    /// match, and the interface timing tuned again with
+    /// [`dig_tune`](Self::dig_tune). The sampling rates are not changed.
+    ///
+    /// Returns `Err(Ad9361Error::InvalidArgument)` for a combination the port
+    /// does not support: `half_duplex`, `single_data_rate` or `single_port`
+    /// in LVDS mode, or `half_duplex` or `single_port` with `full_port`
+    pub fn set_digital_interface_mode(
+        &mut self,
+        cfg: DigitalInterfaceConfig,
+    ) -> Result<(), Ad9361Error> {
+        let lvds = self.get_interface_electrical_mode()?;
+        if (lvds
+            && (cfg.half_duplex || cfg.single_data_rate || cfg.single_port))
+            || (cfg.full_port && (cfg.half_duplex || cfg.single_port))
+        {
+            return Err(Ad9361Error::InvalidArgument);
+        }
+        let mut conf = cfg.to_registers();
+        if lvds {
+            conf[2] |= LVDS_MODE;
+        }
+        // DELAY_RX_DATA shares the register, and is set from the
+        // initialisation parameters
+        conf[1] |=
+            self.read_register(REG_PARALLEL_PORT_CONF_2)? & DELAY_RX_DATA_MASK;
+
+        let previous = self.read_ensm_state()?;
+        let (_active, inner_ptr) = self.inner_ptr()?;
+        unsafe {
+            let alert = EnsmState::Alert as u8;
+            bindings::ad9361_ensm_force_state(inner_ptr, alert);
+        }
+        let result = (REG_PARALLEL_PORT_CONF_1..).zip(conf).try_for_each(
+            |(register, value)| self.write_register(register, value),
+        );
+        if previous != EnsmState::Alert && previous != EnsmState::Unknown {
+            unsafe {
+                bindings::ad9361_ensm_force_state(inner_ptr, previous.into());
+            }
+        }
+        result?;
+
+        unsafe {
+            (*(*inner_ptr).pdata).port_ctrl.pp_conf = conf;
+        }
+        let params = &mut self.params.0;
+        params.pp_tx_swap_enable = cfg.pp_tx_swap.into();
+        params.pp_rx_swap_enable = cfg.pp_rx_swap.into();
+        params.tx_channel_swap_enable = cfg.tx_channel_swap.into();
+        params.rx_channel_swap_enable = cfg.rx_channel_swap.into();
+        params.rx_frame_pulse_mode_enable = cfg.rx_frame_pulse_mode.into();
+        params.two_t_two_r_timing_enable = cfg.two_t_two_r_timing.into();
+        params.invert_data_bus_enable = cfg.invert_data_bus.into();
+        params.invert_data_clk_enable = cfg.invert_data_clk.into();
+        params.fdd_alt_word_order_enable = cfg.fdd_alt_word_order.into();
+        params.invert_rx_frame_enable = cfg.invert_rx_frame.into();
+        params.fdd_rx_rate_2tx_enable = cfg.fdd_rx_rate_2tx.into();
+        params.swap_ports_enable = cfg.swap_ports.into();
+        params.single_data_rate_enable = cfg.single_data_rate.into();
+        params.half_duplex_mode_enable = cfg.half_duplex.into();
+        params.single_port_mode_enable = cfg.single_port.into();
+        params.full_port_enable = cfg.full_port.into();
+        params.full_duplex_swap_bits_enable = cfg.full_duplex_swap_bits.into();
+        Ok(())
+    }
+    /// Reverse the bit order of the data bus (true) or use the normal order
+    /// (false)
+    ///
+    /// A shortcut for changing `invert_data_bus` with
+    /// [`set_digital_interface_mode`](Self::set_digital_interface_mode)
+    pub fn set_invert_data_bus(
+        &mut self,
+        invert: bool,
+    ) -> Result<(), Ad9361Error> {
+        let mut cfg = self.get_digital_interface_mode()?;
+        cfg.invert_data_bus = invert;
+        self.set_digital_interface_mode(cfg)
+    }
+    /// Invert DATA_CLK (true) or use the normal clock edge (false)
+    ///
+    /// A shortcut for changing `invert_data_clk` with
+    /// [`set_digital_interface_mode`](Self::set_digital_interface_mode)
+    pub fn set_invert_data_clk(
+        &mut self,
+        invert: bool,
+    ) -> Result<(), Ad9361Error> {
+        let mut cfg = self.get_digital_interface_mode()?;
+        cfg.invert_data_clk = invert;
+        self.set_digital_interface_mode(cfg)
+    }
+    /// Invert RX_FRAME (true) or use the normal polarity (false)
+    ///
+    /// A shortcut for changing `invert_rx_frame` with
+    /// [`set_digital_interface_mode`](Self::set_digital_interface_mode)
+    pub fn set_invert_rx_frame(
+        &mut self,
+        invert: bool,
+    ) -> Result<(), Ad9361Error> {
+        let mut cfg = self.get_digital_interface_mode()?;
+        cfg.invert_rx_frame = invert;
+        self.set_digital_interface_mode(cfg)
+    }
+    /// Select single data rate (true) or double data rate (false) on the
+    /// parallel port
+    ///
+    /// A shortcut for changing `single_data_rate` with
+    /// [`set_digital_interface_mode`](Self::set_digital_interface_mode), which
+    /// also updates the port configuration held by the C driver. The clock
+    /// tree and sampling rates tracked by the driver do not depend on the data
+    /// rate of the port, so they are unchanged. The FPGA or baseband processor
+    /// must be switched in lockstep, as no valid samples are transferred until
+    /// both ends agree, and the interface timing should then be tuned again.
+    ///
+    /// Single data rate is only available in CMOS mode. Returns
+    /// `Err(Ad9361Error::InvalidArgument)` if `sdr` is set in LVDS mode
+    pub fn set_single_data_rate(
+        &mut self,
+        sdr: bool,
+    ) -> Result<(), Ad9361Error> {
+        let mut cfg = self.get_digital_interface_mode()?;
+        cfg.single_data_rate = sdr;
+        self.set_digital_interface_mode(cfg)
+    }
+    /// Get the configuration of the parallel data port
+    ///
+    /// See [`set_digital_interface_mode`](Self::set_digital_interface_mode)
+    pub fn get_digital_interface_mode(
+        &self,
+    ) -> Result<DigitalInterfaceConfig, Ad9361Error> {
+        let mut conf = [0; 3];
+        for (register, value) in (REG_PARALLEL_PORT_CONF_1..).zip(&mut conf) {
+            *value = self.read_register(register)?;
+        }
+        Ok(DigitalInterfaceConfig::from_registers(conf))
+    }
+
+    /// Set the options used by [`dig_tune`](Self::dig_tune)
+    ///
+    /// `skip_mode` selects which paths are tuned
+    ///
+    /// | `skip_mode` | Behaviour            |
+    /// |-------------|----------------------|
+    /// | 0           | Tune RX and TX       |
+    /// | 1           | Tune RX only         |
+    /// | 2           | Skip tuning entirely |
+    ///
+    /// When `fir_disable` is set, the RX and TX FIR filters are bypassed
+    /// during tuning. These options are also set by the
+    /// `digital_interface_tune_skip_mode` and
+    /// `digital_interface_tune_fir_disable` initialisation parameters, and
+    /// take effect the next time the digital interface is tuned.
+    ///
+    /// Returns `Err` if `skip_mode` is greater than 2
+    pub fn set_digital_tune_options(
+        &mut self,
+        skip_mode: u8,
+        fir_disable: bool,
+    ) -> Result<(), Ad9361Error> {
+        if skip_mode > 2 {
+            return Err(Ad9361Error::InvalidArgument);
+        }
+        assert!(
+            !self.inner.is_null(),
+            "Must call init() method before accessing ad9361"
+        );
+        let (_active, inner_ptr) = self.inner_ptr()?;
+        unsafe {
+            let pdata = (*inner_ptr).pdata;
+            (*pdata).dig_interface_tune_skipmode = skip_mode;
+            (*pdata).dig_interface_tune_fir_disable = fir_disable;
+        }
+        self.params.0.digital_interface_tune_skip_mode = skip_mode;
+        self.params.0.digital_interface_tune_fir_disable = fir_disable.into();
+
+        Ok(())
+    }
+    /// Get the options used by the digital interface tuning, as
+    /// (`skip_mode`, `fir_disable`)
+    ///
+    /// See [`set_digital_tune_options`](Self::set_digital_tune_options)
+    pub fn get_digital_tune_options(&self) -> (u8, bool) {
+        assert!(
+            !self.inner.is_null(),
+            "Must call init() method before accessing ad9361"
+        );
+        let inner_ptr = self.inner;
+        unsafe {
+            let pdata = (*inner_ptr).pdata;
+            (
+                (*pdata).dig_interface_tune_skipmode,
+                (*pdata).dig_interface_tune_fir_disable,
+            )
+        }
+    }
+    /// Time allowed for the PRBS checker at each delay setting, in
+    /// milliseconds
+    const DIG_TUNE_SETTLE_MS: u32 = 4;
+
+    /// Tune the clock and data delays of the digital interface
+    ///
+    /// The AD9361 does not check PRBS data itself, so tuning needs the PRBS
+    /// checker in the baseband processor (usually an FPGA). For each clock
+    /// delay, and then each data delay, the delay is set and `check` is
+    /// called after 4 ms to read the checker. A delay passes if `check`
+    /// returns a [`PrbsResult`] that has [`passed`](PrbsResult::passed). The
+    /// centre of the widest passing window is then selected.
+    ///
+    /// The RX interface is tuned with the AD9361 injecting PRBS data on the
+    /// RX port. The TX interface is tuned with the digital loopback enabled,
+    /// so the PRBS data generated by the baseband processor on the TX port is
+    /// returned on the RX port. `check` is passed `true` while the TX
+    /// interface is tuned. Which interfaces are tuned, and whether the FIR
+    /// filters are bypassed, is set by
+    /// [`set_digital_tune_options`](Self::set_digital_tune_options).
+    ///
+    /// If `max_freq` is non-zero, the sampling rate is set to `max_freq` Hz
+    /// while tuning, so the delays are tuned for the tightest timing. The
+    /// transmit channels are muted while tuning, and the clock tree, FIR
+    /// filters, BIST modes and mutes are restored afterwards. If tuning or a
+    /// restore fails, the remaining restores are still attempted and the
+    /// first error is returned.
+    ///
+    /// With [`DigTuneFlags::BE_VERBOSE`] the pass/fail map of each sweep is
+    /// logged at debug level, with `o` for a passing delay and `#` for a
+    /// failing one. The chosen delays are returned with the width of their
+    /// passing window.
+    ///
+    /// Returns `Err(Ad9361Error::Io)` if no delay passed for an interface. The
+    /// delays of that interface are left at the last setting swept. Returns
+    /// `Err(Ad9361Error::InvalidArgument)` if [`DigTuneFlags::DO_IDELAY`] or
+    /// [`DigTuneFlags::DO_ODELAY`] is set
+    pub fn dig_tune(
+        &mut self,
+        max_freq: u32,
+        flags: DigTuneFlags,
+        mut check: impl FnMut(bool) -> PrbsResult,
+    ) -> Result<DigTuneResult, Ad9361Error>
+    where
+        DELAY: hal::Delay,
+    {
+        if flags.contains(DigTuneFlags::DO_IDELAY)
+            || flags.contains(DigTuneFlags::DO_ODELAY)
+        {
+            return Err(Ad9361Error::InvalidArgument);
+        }
+        let mut tuned = DigTuneResult { rx: None, tx: None };
+        let (skip_mode, fir_disable) = self.get_digital_tune_options();
+        if skip_mode == 2 {
+            return Ok(tuned);
+        }
+
+        let path_clks = self.get_trx_path_clks()?;
+        let fir_enabled =
+            (self.get_rx_fir_en_dis()?, self.get_tx_fir_en_dis()?);
+        let prbs = self.get_bist_prbs();
+        let loopback = self.get_bist_loopback();
+        let muted = (
+            self.is_tx_muted(TxChannel::Tx1),
+            self.is_tx_muted(TxChannel::Tx2),
+        );
+
+        self.tx_mute(TxChannel::Tx1, true)?;
+        self.tx_mute(TxChannel::Tx2, true)?;
+        let result = (|| {
+            if max_freq != 0 {
+                self.set_rx_sampling_freq(max_freq)?;
+            }
+            if fir_disable {
+                self.set_rx_fir_en_dis(false)?;
+                self.set_tx_fir_en_dis(false)?;
+            }
+
+            self.bist_loopback(LoopbackMode::Disabled)?;
+            self.bist_prbs(BistMode::InjectRx)?;
+            tuned.rx = Some(self.dig_tune_delay(false, flags, &mut check)?);
+
+            if skip_mode == 0 {
+                self.bist_prbs(BistMode::Disable)?;
+                self.bist_loopback(LoopbackMode::Enabled)?;
+                tuned.tx = Some(self.dig_tune_delay(true, flags, &mut check)?);
+            }
+            Ok(tuned)
+        })();
+
+        // Attempt every restore, keeping the first error
+        let mut restored = self.bist_loopback(loopback);
+        restored = restored.and(self.bist_prbs(prbs));
+        if max_freq != 0 {
+            restored = restored
+                .and(self.set_trx_path_clks(&path_clks.0, &path_clks.1));
+        }
+        if fir_disable {
+            restored = restored.and(self.set_rx_fir_en_dis(fir_enabled.0));
+            restored = restored.and(self.set_tx_fir_en_dis(fir_enabled.1));
+        }
+        restored =
+            restored.and(self.tx_mute(TxChannel::Tx1, muted.0).map(drop));
+        restored =
+            restored.and(self.tx_mute(TxChannel::Tx2, muted.1).map(drop));
+        result.and_then(|tuned| restored.map(|()| tuned))
+    }
+    /// Sweep the clock and data delays of the RX (or TX) interface, and set
+    /// the centre of the widest passing window
+    fn dig_tune_delay(
+        &mut self,
+        tx: bool,
+        flags: DigTuneFlags,
+        check: &mut impl FnMut(bool) -> PrbsResult,
+    ) -> Result<DigTuneDelay, Ad9361Error>
+    where
+        DELAY: hal::Delay,
+    {
+        let register = if tx {
+            REG_TX_CLOCK_DATA_DELAY
+        } else {
+            REG_RX_CLOCK_DATA_DELAY
+        };
+
+        // Clock delays, then data delays
+        let mut passed = [[false; 16]; 2];
+        for (sweep, row) in passed.iter_mut().enumerate() {
+            for (delay, pass) in (0u8..).zip(row.iter_mut()) {
+                let value = if sweep == 0 { delay << 4 } else { delay };
+                self.write_register(register, value)?;
+                self.delay.delay_ms(Self::DIG_TUNE_SETTLE_MS);
+                let result = check(tx);
+                if flags.contains(DigTuneFlags::BE_MOREVERBOSE) {
+                    debug!("AD936x: delay {:#04x}: {:?}", value, result);
+                }
+                *pass = result.passed();
+            }
+        }
+
+        let direction = if tx { "TX" } else { "RX" };
+        if flags.contains(DigTuneFlags::BE_VERBOSE) {
+            debug!("AD936x: {} delay     0123456789abcdef", direction);
+            debug!("AD936x: {} clock {}", direction, passing_map(&passed[0]));
+            debug!("AD936x: {} data  {}", direction, passing_map(&passed[1]));
+        }
+
+        let (clock_start, clock_width) = passing_window(&passed[0]);
+        let (data_start, data_width) = passing_window(&passed[1]);
+        if clock_width == 0 && data_width == 0 {
+            warn!("AD936x: {} digital interface tuning failed", direction);
+            return Err(Ad9361Error::Io);
+        }
+        let delay = if data_width > clock_width {
+            DigTuneDelay {
+                clock_delay: 0,
+                data_delay: data_start + data_width / 2,
+                window: data_width,
+            }
+        } else {
+            DigTuneDelay {
+                clock_delay: clock_start + clock_width / 2,
+                data_delay: 0,
+                window: clock_width,
+            }
+        };
+        self.write_register(
+            register,
+            (delay.clock_delay << 4) | delay.data_delay,
+        )?;
+        Ok(delay)
+    }
+}
+
+/// Pass/fail map of a delay sweep, `o` for pass and `#` for fail
+struct PassingMap([u8; 16]);
+impl core::fmt::Display for PassingMap {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.write_str(
+            core::str::from_utf8(&self.0).map_err(|_| core::fmt::Error)?,
+        )
+    }
+}
+fn passing_map(passed: &[bool; 16]) -> PassingMap {
+    let mut map = [b'#'; 16];
+    for (c, &pass) in map.iter_mut().zip(passed.iter()) {
+        if pass {
+            *c = b'o';
+        }
+    }
+    PassingMap(map)
+}
+
+/// Start and width of the longest run of passing delays
+fn passing_window(passed: &[bool; 16]) -> (u8, u8) {
+    let mut best = (0, 0);
+    let mut start = 0;
+    for (delay, &pass) in (0u8..).zip(passed.iter()) {
+        if !pass {
+            start = delay + 1;
+        } else if delay + 1 - start > best.1 {
+            best = (start, delay + 1 - start);
+        }
+    }
+    best
+}
+
+/// FIR filter methods
+///
+impl<'a, SPI, DELAY, RESETB, SYNC> Ad9361<'a, SPI, DELAY, RESETB, SYNC> {
+    /// Read back the FIR coefficient RAM and compare it with `coefficients`.
+    /// The TX and RX FIR registers are separated by `offset`
+    fn verify_fir(
+        &self,
+        offset: u16,
+        destination: u32,
+        coefficients: &[i16],
+    ) -> Result<bool, Ad9361Error> {
+        const FIR_START_CLK: u8 = 0x02;
+        let filter_conf = 0x065 + offset;
+        let coef_address = 0x060 + offset;
+        let read_data = 0x063 + offset;
+
+        let conf = self.read_register(filter_conf)?;
+        let num_taps = (coefficients.len().max(1) - 1) / 16;
+        let selection = match destination & 3 {
+            3 => 1..=2, // both channels
+            fir => fir..=fir,
+        };
+
+        let mut matches = true;
+        for fir in selection {
+            let select = ((num_taps as u8 & 7) << 5) | ((fir as u8 & 3) << 3);
+            self.write_register(filter_conf, select | FIR_START_CLK)?;
+
+            for (address, expected) in coefficients.iter().enumerate() {
+                self.write_register(coef_address, address as u8)?;
+                let lsb = self.read_register(read_data)?;
+                let msb = self.read_register(read_data + 1)?;
+
+                if i16::from_le_bytes([lsb, msb]) != *expected {
+                    matches = false;
+                }
+            }
+        }
+        self.write_register(filter_conf, conf)?;
+
+        Ok(matches)
+    }
+    /// Set the TX FIR configuration
+    ///
+    /// Returns `Err(Ad9361Error::InvalidArgument)` if the configuration is
+    /// not supported by the filter, see [`Ad9361TxFir::validate`]
+    pub fn set_tx_fir_config(
+        &mut self,
+        config: Ad9361TxFir,
+    ) -> Result<(), Ad9361Error> {
+        assert!(
+            !self.inner.is_null(),
+            "Must call init() method before accessing ad9361"
+        );
+        if let Err(e) = config.validate() {
+            warn!("AD936x: TX FIR configuration: {}", e);
+            return Err(Ad9361Error::InvalidArgument);
+        }
+        let (_active, inner_ptr) = self.inner_ptr()?;
+        let status =
+            unsafe { bindings::ad9361_set_tx_fir_config(inner_ptr, config.0) };
+        if status == 0 {
+            Ok(())
+        } else {
+            Err(status.into())
+        }
+    }
+    /// Set the RX FIR configuration
+    ///
+    /// Returns `Err(Ad9361Error::InvalidArgument)` if the configuration is
+    /// not supported by the filter, see [`Ad9361RxFir::validate`]
+    pub fn set_rx_fir_config(
+        &mut self,
+        config: Ad9361RxFir,
+    ) -> Result<(), Ad9361Error> {
+        assert!(
+            !self.inner.is_null(),
+            "Must call init() method before accessing ad9361"
+        );
+        if let Err(e) = config.validate() {
+            warn!("AD936x: RX FIR configuration: {}", e);
+            return Err(Ad9361Error::InvalidArgument);
+        }
+        let (_active, inner_ptr) = self.inner_ptr()?;
+        let status =
+            unsafe { bindings::ad9361_set_rx_fir_config(inner_ptr, config.0) };
+        if status == 0 {
+            Ok(())
+        } else {
+            Err(status.into())
+        }
+    }
+    /// Read back the TX FIR configuration currently loaded for the selected
+    /// channel. Channel 0 = TX1, 1 = TX2
+    ///
+    /// Returns `Ok(None)` if no TX FIR configuration has been loaded, and
+    /// the coefficient RAM is empty
+    pub fn get_tx_fir_config(
+        &self,
+        channel: impl Into<u8>,
+    ) -> Result<Option<Ad9361TxFir>, Ad9361Error> {
+        let channel = channel.into();
+        if channel > 1 {
+            return Err(Ad9361Error::InvalidArgument);
+        }
+        assert!(
+            !self.inner.is_null(),
+            "Must call init() method before accessing ad9361"
+        );
+        let (_active, inner_ptr) = self.inner_ptr()?;
+        let mut config = bindings::AD9361_TXFIRConfig::default();
+        let status = unsafe {
+            bindings::ad9361_get_tx_fir_config(inner_ptr, channel, &mut config)
+        };
+        if status != 0 {
+            return Err(status.into());
+        }
+        let fir = Ad9361TxFir(config);
+        if fir.get_tx_coef().iter().all(|&c| c == 0) {
+            Ok(None)
+        } else {
+            Ok(Some(fir))
+        }
+    }
+    /// Read back the RX FIR configuration currently loaded for the selected
+    /// channel. Channel 0 = RX1, 1 = RX2
+    ///
+    /// Returns `Ok(None)` if no RX FIR configuration has been loaded, and
+    /// the coefficient RAM is empty
+    pub fn get_rx_fir_config(
+        &self,
+        channel: impl Into<u8>,
+    ) -> Result<Option<Ad9361RxFir>, Ad9361Error> {
+        let channel = channel.into();
+        if channel > 1 {
+            return Err(Ad9361Error::InvalidArgument);
+        }
+        assert!(
+            !self.inner.is_null(),
+            "Must call init() method before accessing ad9361"
+        );
+        let (_active, inner_ptr) = self.inner_ptr()?;
+        let mut config = bindings::AD9361_RXFIRConfig::default();
+        let status = unsafe {
+            bindings::ad9361_get_rx_fir_config(inner_ptr, channel, &mut config)
+        };
+        if status != 0 {
+            return Err(status.into());
+        }
+        let fir = Ad9361RxFir(config);
+        if fir.get_rx_coef().iter().all(|&c| c == 0) {
+            Ok(None)
+        } else {
+            Ok(Some(fir))
+        }
+    }
+    /// Set the RX FIR configuration, using the current RX path clocks and RX
+    /// RF bandwidth
+    ///
+    /// The `rx_path_clks` and `rx_bandwidth` fields of `fir` are replaced with
+    /// the path clocks read from the driver and the current RX RF bandwidth,
+    /// so that the clock tree is not left unset. The decimation of `fir` must
+    /// therefore match the current path clocks
+    pub fn apply_rx_fir(
+        &mut self,
+        fir: Ad9361RxFir,
+    ) -> Result<(), Ad9361Error> {
+        let (rx_path_clks, _) = self.get_trx_path_clks()?;
+
+        let mut config = fir.0;
+        config.rx_path_clks = rx_path_clks;
+        config.rx_bandwidth = self.get_rx_rf_bandwidth()?;
+        self.set_rx_fir_config(Ad9361RxFir(config))
+    }
+    /// Verify that the coefficients of `expected` are loaded in the TX FIR
+    /// coefficient RAM
+    ///
+    /// Each coefficient is read back with three SPI transactions, so this
+    /// method requires up to 768 SPI transactions for a 128 tap filter loaded
+    /// to both TX1 and TX2
+    pub fn verify_tx_fir(
+        &self,
+        expected: &Ad9361TxFir,
+    ) -> Result<bool, Ad9361Error> {
+        self.verify_fir(0, expected.0.tx, expected.get_tx_coef())
+    }
+    /// Verify that the coefficients of `expected` are loaded in the RX FIR
+    /// coefficient RAM
+    ///
+    /// Each coefficient is read back with three SPI transactions, so this
+    /// method requires up to 768 SPI transactions for a 128 tap filter loaded
+    /// to both RX1 and RX2
+    pub fn verify_rx_fir(
+        &self,
+        expected: &Ad9361RxFir,
+    ) -> Result<bool, Ad9361Error> {
+        // The RX FIR gain must be cleared whilst reading back coefficients
+        const REG_RX_FILTER_GAIN: u16 = 0x0F6;
+        let gain = self.read_register(REG_RX_FILTER_GAIN)?;
+        self.write_register(REG_RX_FILTER_GAIN, 0)?;
+
+        let matches =
+            self.verify_fir(0x090, expected.0.rx, expected.get_rx_coef());
+
+        self.write_register(REG_RX_FILTER_GAIN, gain)?;
+        matches
+    }
+}
+
+/// Fastlock methods
+///
+impl<'a, SPI, DELAY, RESETB, SYNC> Ad9361<'a, SPI, DELAY, RESETB, SYNC> {
+    /// Reference frequency of the RX or TX synthesizer
+    fn synth_reference_hz(&self, tx: bool) -> Result<u64, Ad9361Error> {
+        assert!(
+            !self.inner.is_null(),
+            "Must call init() method before accessing ad9361"
+        );
+        let (_active, inner_ptr) = self.inner_ptr()?;
+        let clock = if tx {
+            bindings::ad9361_clocks::TX_REFCLK
+        } else {
+            bindings::ad9361_clocks::RX_REFCLK
+        };
+        let rate = unsafe {
+            let scale = (*inner_ptr).ref_clk_scale[clock as usize];
+            bindings::clk_get_rate(inner_ptr, scale)
+        };
+        if rate > 0 {
+            Ok(rate.into())
+        } else {
+            Err(Ad9361Error::InvalidArgument)
+        }
+    }
+
+    /// Number of fastlock profiles for each of RX and TX
+    const FASTLOCK_PROFILES: u8 = 8;
+
+    /// Check that a fastlock profile index is in range
+    fn check_fastlock_profile(profile: u8) -> Result<(), Ad9361Error> {
+        if profile < Self::FASTLOCK_PROFILES {
+            Ok(())
+        } else {
+            Err(Ad9361Error::InvalidArgument)
+        }
+    }
+
+    /// Store the current RX synthesizer settings in fastlock `profile`
+    ///
+    /// Profiles are numbered 0 to 7
+    pub fn rx_fastlock_store(
+        &mut self,
+        profile: u8,
+    ) -> Result<(), Ad9361Error> {
+        Self::check_fastlock_profile(profile)?;
+        assert!(
+            !self.inner.is_null(),
+            "Must call init() method before accessing ad9361"
+        );
+        let (_active, inner_ptr) = self.inner_ptr()?;
+        let status = unsafe {
+            bindings::ad9361_rx_fastlock_store(inner_ptr, profile.into())
+        };
+        if status == 0 {
+            Ok(())
+        } else {
+            Err(status.into())
+        }
+    }
+    /// Recall the RX synthesizer settings stored in fastlock `profile`
+    ///
+    /// Profiles are numbered 0 to 7
+    pub fn rx_fastlock_recall(
+        &mut self,
+        profile: u8,
+    ) -> Result<(), Ad9361Error> {
+        Self::check_fastlock_profile(profile)?;
+        assert!(
+            !self.inner.is_null(),
+            "Must call init() method before accessing ad9361"
+        );
+        let (_active, inner_ptr) = self.inner_ptr()?;
+        let status = unsafe {
+            bindings::ad9361_rx_fastlock_recall(inner_ptr, profile.into())
+        };
+        if status == 0 {
+            Ok(())
+        } else {
+            Err(status.into())
+        }
+    }
+    /// Read back the contents of RX fastlock `profile`, so that it can be
+    /// persisted and later restored with
+    /// [`rx_fastlock_load`](Self::rx_fastlock_load)
+    ///
+    /// Profiles are numbered 0 to 7
+    pub fn rx_fastlock_save(
+        &mut self,
+        profile: u8,
+    ) -> Result<[u8; 16], Ad9361Error> {
+        Self::check_fastlock_profile(profile)?;
+        assert!(
+            !self.inner.is_null(),
+            "Must call init() method before accessing ad9361"
+        );
+        let (_active, inner_ptr) = self.inner_ptr()?;
+        let mut values = [0u8; 16];
+        let status = unsafe {
+            bindings::ad9361_rx_fastlock_save(
+                inner_ptr,
+                profile.into(),
+                values.as_mut_ptr(),
+            )
+        };
+        if status == 0 {
+            Ok(values)
+        } else {
+            Err(status.into())
+        }
+    }
+    /// Write the contents of RX fastlock `profile`, previously read with
+    /// [`rx_fastlock_save`](Self::rx_fastlock_save)
+    ///
+    /// Profiles are numbered 0 to 7
+    pub fn rx_fastlock_load(
+        &mut self,
+        profile: u8,
+        values: &[u8; 16],
+    ) -> Result<(), Ad9361Error> {
+        Self::check_fastlock_profile(profile)?;
+        assert!(
+            !self.inner.is_null(),
+            "Must call init() method before accessing ad9361"
+        );
+        let (_active, inner_ptr) = self.inner_ptr()?;
+        // The C driver does not modify the values
+        let values_ptr = values.as_ptr() as *mut u8;
+        let status = unsafe {
+            bindings::ad9361_rx_fastlock_load(
+                inner_ptr,
+                profile.into(),
+                values_ptr,
+            )
+        };
+        if status == 0 {
+            Ok(())
+        } else {
+            Err(status.into())
+        }
+    }
+
+    /// Store the current TX synthesizer settings in fastlock `profile`
+    ///
+    /// Profiles are numbered 0 to 7
+    pub fn tx_fastlock_store(
+        &mut self,
+        profile: u8,
+    ) -> Result<(), Ad9361Error> {
+        Self::check_fastlock_profile(profile)?;
+        assert!(
+            !self.inner.is_null(),
+            "Must call init() method before accessing ad9361"
+        );
+        let (_active, inner_ptr) = self.inner_ptr()?;
+        let status = unsafe {
+            bindings::ad9361_tx_fastlock_store(inner_ptr, profile.into())
+        };
+        if status == 0 {
+            Ok(())
+        } else {
+            Err(status.into())
+        }
+    }
+    /// Recall the TX synthesizer settings stored in fastlock `profile`
+    ///
+    /// Profiles are numbered 0 to 7
+    pub fn tx_fastlock_recall(
+        &mut self,
+        profile: u8,
+    ) -> Result<(), Ad9361Error> {
+        Self::check_fastlock_profile(profile)?;
+        assert!(
+            !self.inner.is_null(),
+            "Must call init() method before accessing ad9361"
+        );
+        let (_active, inner_ptr) = self.inner_ptr()?;
+        let status = unsafe {
+            bindings::ad9361_tx_fastlock_recall(inner_ptr, profile.into())
+        };
+        if status == 0 {
+            Ok(())
+        } else {
+            Err(status.into())
+        }
+    }
+    /// Read back the contents of TX fastlock `profile`, so that it can be
+    /// persisted and later restored with
+    /// [`tx_fastlock_load`](Self::tx_fastlock_load)
+    ///
+    /// Profiles are numbered 0 to 7
+    pub fn tx_fastlock_save(
+        &mut self,
+        profile: u8,
+    ) -> Result<[u8; 16], Ad9361Error> {
+        Self::check_fastlock_profile(profile)?;
+        assert!(
+            !self.inner.is_null(),
+            "Must call init() method before accessing ad9361"
+        );
+        let (_active, inner_ptr) = self.inner_ptr()?;
+        let mut values = [0u8; 16];
+        let status = unsafe {
+            bindings::ad9361_tx_fastlock_save(
+                inner_ptr,
+                profile.into(),
+                values.as_mut_ptr(),
+            )
+        };
+        if status == 0 {
+            Ok(values)
+        } else {
+            Err(status.into())
+        }
+    }
+    /// Write the contents of TX fastlock `profile`, previously read with
+    /// [`tx_fastlock_save`](Self::tx_fastlock_save)
+    ///
+    /// Profiles are numbered 0 to 7
+    pub fn tx_fastlock_load(
+        &mut self,
+        profile: u8,
+        values: &[u8; 16],
+    ) -> Result<(), Ad9361Error> {
+        Self::check_fastlock_profile(profile)?;
+        assert!(
+            !self.inner.is_null(),
+            "Must call init() method before accessing ad9361"
+        );
+        let (_active, inner_ptr) = self.inner_ptr()?;
+        // The C driver does not modify the values
+        let values_ptr = values.as_ptr() as *mut u8;
+        let status = unsafe {
+            bindings::ad9361_tx_fastlock_load(
+                inner_ptr,
+                profile.into(),
+                values_ptr,
+            )
+        };
+        if status == 0 {
+            Ok(())
+        } else {
+            Err(status.into())
+        }
+    }
+
+    /// Decode the RX LO frequency stored in a saved fastlock profile, without
+    /// recalling the profile.
+    ///
+    /// A fastlock profile consists of 16 bytes:
+    ///
+    /// | Byte  | Content                                           |
+    /// |-------|---------------------------------------------------|
+    /// | 0     | Synthesizer integer word \[7:0\]                  |
+    /// | 1     | Synthesizer integer word \[10:8\] in bits \[2:0\] |
+    /// | 2     | Synthesizer fractional word \[7:0\]               |
+    /// | 3     | Synthesizer fractional word \[15:8\]              |
+    /// | 4     | Synthesizer fractional word \[22:16\]             |
+    /// | 5     | VCO bias reference, VCO varactor                  |
+    /// | 6     | VCO bias TCF, charge pump current                 |
+    /// | 7     | Initial charge pump current                       |
+    /// | 8-13  | Loop filter settings                              |
+    /// | 14    | VCO varactor reference                            |
+    /// | 15    | VCO divider in bits \[3:0\]                       |
+    ///
+    /// The LO frequency is f_ref * (integer + fractional / 8388593) / 2^(VCO
+    /// divider + 1), where f_ref is the current synthesizer reference
+    pub fn decode_rx_fastlock_profile(
+        &self,
+        values: &[u8; 16],
+    ) -> Result<u64, Ad9361Error> {
+        let reference = self.synth_reference_hz(false)?;
+
+        Ok(synth::fastlock_profile_frequency(reference, values))
+    }
+    /// Decode the TX LO frequency stored in a saved fastlock profile, without
+    /// recalling the profile.
+    ///
+    /// See [`decode_rx_fastlock_profile`](Self::decode_rx_fastlock_profile)
+    /// for the layout of the profile
+    pub fn decode_tx_fastlock_profile(
+        &self,
+        values: &[u8; 16],
+    ) -> Result<u64, Ad9361Error> {
+        let reference = self.synth_reference_hz(true)?;
+
+        Ok(synth::fastlock_profile_frequency(reference, values))
+    }
+
+    /// Read the divider settings of the RX or TX synthesizer
+    fn lo_dividers(&self, tx: bool) -> Result<LoDividers, Ad9361Error> {
+        let base = if tx {
+            REG_TX_INTEGER_BYTE_0
+        } else {
+            REG_RX_INTEGER_BYTE_0
+        };
+        let int0 = self.read_register(base)?;
+        let int1 = self.read_register(base + INTEGER_BYTE_1)?;
+        let fract0 = self.read_register(base + FRACT_BYTE_0)?;
+        let fract1 = self.read_register(base + FRACT_BYTE_1)?;
+        let fract2 = self.read_register(base + FRACT_BYTE_2)?;
+        let dividers = self.read_register(REG_RFPLL_DIVIDERS)?;
+
+        Ok(LoDividers {
+            integer: u16::from_le_bytes([int0, int1 & 0x07]),
+            fractional: u32::from_le_bytes([fract0, fract1, fract2 & 0x7F, 0]),
+            modulus: synth::RFPLL_MODULUS as u32,
+            vco_divider: if tx { dividers >> 4 } else { dividers & 0x0F },
+        })
+    }
+    /// Get the integer and fractional divider words, modulus and VCO divider
+    /// programmed in the RX synthesizer
+    ///
+    /// See [`LoDividers`] for the frequency formula
+    pub fn get_rx_lo_dividers(&self) -> Result<LoDividers, Ad9361Error> {
+        self.lo_dividers(false)
+    }
+    /// Get the integer and fractional divider words, modulus and VCO divider
+    /// programmed in the TX synthesizer
+    ///
+    /// See [`LoDividers`] for the frequency formula
+    pub fn get_tx_lo_dividers(&self) -> Result<LoDividers, Ad9361Error> {
+        self.lo_dividers(true)
+    }
+
+    /// Returns true if retuning the RX (`rx` set) or TX (`rx` clear) LO from
+    /// its current frequency to `new_hz` crosses a VCO band boundary, and so
+    /// requires a VCO calibration
+    ///
+    /// The RF VCOs cover 6 GHz to 12 GHz, and the LO is derived from the VCO
+    /// by a divider of 2 to 128. The band boundaries are therefore at 3 GHz,
+    /// 1.5 GHz, 750 MHz, 375 MHz, 187.5 MHz and 93.75 MHz. Retuning within a
+    /// band does not change the VCO divider.
+    ///
+    /// Returns `Err` if `new_hz` is outside the range of the synthesizer
+    pub fn lo_change_requires_vco_cal(
+        &self,
+        new_hz: u64,
+        rx: bool,
+    ) -> Result<bool, Ad9361Error> {
+        let current_hz = if rx {
+            self.get_rx_lo_freq()?
+        } else {
+            self.get_tx_lo_freq()?
+        };
+        let new = synth::rfpll_vco_divider(new_hz)
+            .ok_or(Ad9361Error::InvalidArgument)?;
+        let current = synth::rfpll_vco_divider(current_hz)
+            .ok_or(Ad9361Error::InvalidArgument)?;
+
+        Ok(new != current)
+    }
+}
+
+/// Gain table methods
+///
+impl<'a, SPI, DELAY, RESETB, SYNC> Ad9361<'a, SPI, DELAY, RESETB, SYNC> {
+    /// Set a new gain table for both receivers
+    ///
+    /// The driver reloads the table when the RX LO frequency moves into a
+    /// different band of `gain_table`. See
+    /// [`load_gain_table`](Self::load_gain_table)
+    pub fn set_gain_table(
+        &mut self,
+        gain_table: &'a mut GainTable,
+    ) -> Result<(), Ad9361Error> {
+        const RX1_RX2: u8 = 3; // both receivers
+        self.load_gain_table(gain_table, RX1_RX2)
+    }
+    /// Load a gain table into the selected receivers. `rx_id` 1 = RX1, 2 =
+    /// RX2, 3 = both
+    ///
+    /// The table is loaded for the current RX LO frequency. When the RX LO
+    /// frequency moves into a different band of `gain_table`, see
+    /// [`GainTable::frequency_range`], the driver loads the table for that
+    /// band. A table from [`GainTable::new_from_recommended`] has a single
+    /// band covering every RX LO frequency, and stays loaded. With a table
+    /// from [`GainTable::new_multiband`], the RX LO frequency must be kept
+    /// within its bands: outside them the driver does not find a band and
+    /// loads its first table slot.
+    ///
+    /// `gain_table` holds self-referential pointers, which are set by this
+    /// method, and the driver keeps a pointer to it in order to reload the
+    /// table when the RX LO frequency changes. Therefore `gain_table` is
+    /// borrowed for the lifetime of this `Ad9361`
+    ///
+    /// Returns `Err(Ad9361Error::InvalidArgument)` if any band of
+    /// `gain_table` is not of the kind set by `split_gain_table_mode_enable`
+    /// in the initialisation parameters
+    pub fn load_gain_table(
+        &mut self,
+        gain_table: &'a mut GainTable,
+        rx_id: u8,
+    ) -> Result<(), Ad9361Error> {
+        if !(1..=3).contains(&rx_id) {
+            return Err(Ad9361Error::InvalidArgument);
+        }
+        let kind = if self.params.0.split_gain_table_mode_enable != 0 {
+            GainTableKind::Split
+        } else {
+            GainTableKind::Full
+        };
+        if !gain_table.is_kind(kind) {
+            warn!("AD936x: gain table has a band that is not {:?}", kind);
+            return Err(Ad9361Error::InvalidArgument);
+        }
+        let rx_lo = self.rx_lo_hz()?;
+
+        let (_active, inner_ptr) = self.inner_ptr()?;
+        let status = unsafe {
+            // set new gt table
+            (*inner_ptr).gt_info = gain_table.set_ptr();
+            (*inner_ptr).current_table = 4_294_967_295;
+            // re-run setup
+            bindings::ad9361_load_gt(inner_ptr, rx_lo, rx_id.into())
+        };
+        if status == 0 {
+            Ok(())
+        } else {
+            Err(status.into())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::*;
+    #[cfg(not(feature = "eh1"))]
+    use embedded_hal::{blocking, digital};
+    #[cfg(feature = "eh1")]
+    use embedded_hal_1::{delay, digital, spi};
+    use serial_test::serial;
+
+    use std::collections::HashMap;
+
+    // Dummy reset pin, active low
+    #[derive(Default)]
+    struct DummyResetB {}
+    #[cfg(not(feature = "eh1"))]
+    impl digital::v2::OutputPin for DummyResetB {
+        type Error = ();
+
+        fn set_low(&mut self) -> Result<(), ()> {
+            trace!("resetb asserted!");
+            Ok(())
+        }
+        fn set_high(&mut self) -> Result<(), ()> {
+            trace!("resetb deasserted!");
+            Ok(())
+        }
+    }
+    #[cfg(feature = "eh1")]
+    impl digital::ErrorType for DummyResetB {
+        type Error = core::convert::Infallible;
+    }
+    #[cfg(feature = "eh1")]
+    impl digital::OutputPin for DummyResetB {
+        fn set_low(&mut self) -> Result<(), Self::Error> {
+            trace!("resetb asserted!");
+            Ok(())
+        }
+        fn set_high(&mut self) -> Result<(), Self::Error> {
+            trace!("resetb deasserted!");
+            Ok(())
+        }
+    }
+
+    // Delay with the embedded-hal version selected by the `eh1` feature
+    #[cfg(not(feature = "eh1"))]
+    type TestDelay = linux_embedded_hal::Delay;
+    #[cfg(feature = "eh1")]
+    struct TestDelay {}
+    #[cfg(feature = "eh1")]
+    impl delay::DelayNs for TestDelay {
+        fn delay_ns(&mut self, ns: u32) {
+            std::thread::sleep(std::time::Duration::from_nanos(ns.into()));
+        }
+    }
+
+    // Dummy SPI interface that is actually a very shallow implementation of the
+    // AD9361 register interface
+    struct DummySPI {
+        registers: HashMap<u16, u8>,
+        // Gain table RAM, by receiver and address
+        gain_tables: HashMap<(usize, u8), [u8; 3]>,
+        transfers: usize,
+    }
+    impl Default for DummySPI {
+        fn default() -> DummySPI {
+            let registers = HashMap::with_capacity(4096);
+            DummySPI {
+                registers,
+                gain_tables: Default::default(),
+                transfers: 0,
+            }
+        }
+    }
+    impl DummySPI {
+        fn transfer_bytes(&mut self, words: &mut [u8]) {
+            self.transfers += 1;
+            let transaction = transaction::Ad9361Transaction(words);
+            let register = transaction.register();
+            let value = transaction.value();
+
+            trace!("spi_transaction! {:?} {:x?}", transaction, words);
+
+            if transaction.is_write() {
+                // Save values. Multi-byte transfers decrement the address
+                self.registers.insert(register, value);
+                for i in 1..transaction.length() {
+                    self.registers.insert(register - i as u16, words[2 + i]);
+                }
+                // ENSM config 1: forcing a state moves the ENSM to it
+                let ensm = (0..transaction.length())
+                    .find(|&i| register - i as u16 == 0x14)
+                    .map(|i| words[2 + i]);
+                if let Some(config) = ensm {
+                    let fdd = self.registers.get(&0x13).copied().unwrap_or(0)
+                        & 0x01
+                        != 0;
+                    let state = if config & 0x20 != 0 {
+                        if fdd {
+                            0xA // FDD
+                        } else {
+                            0x6 // Tx
+                        }
+                    } else if config & 0x40 != 0 {
+                        0x8 // Rx
+                    } else if config & 0x05 != 0 {
+                        0x5 // Alert
+                    } else {
+                        0x0 // Sleep/Wait
+                    };
+                    self.registers.insert(0x17, state);
+                }
+                // Gain table config: the write strobe stores the write data
+                // at the gain table address, for the selected receivers
+                let config = (0..transaction.length())
+                    .find(|&i| register - i as u16 == 0x137)
+                    .map(|i| words[2 + i]);
+                if let Some(config) =
+                    config.filter(|c| c & WRITE_GAIN_TABLE != 0)
+                {
+                    let address = self.registers[&0x130];
+                    let data =
+                        [0x131, 0x132, 0x133].map(|r| self.registers[&r]);
+                    for rx in 0..2 {
+                        if config & (0x08 << rx) != 0 {
+                            self.gain_tables.insert((rx, address), data);
+                        }
+                    }
+                }
+            } else {
+                for i in 0..transaction.length() {
+                    let reg = register - i as u16;
+                    // Recall value (except for options below)
+                    if let Some(value) = self.registers.get(&reg) {
+                        // Recall
+                        words[2 + i] = *value;
+                    }
+                }
+            }
+
+            // Gain table read data, from the gain table of the selected
+            // receiver at the gain table address
+            if (0x134..=0x136).contains(&register) {
+                let config = self.registers.get(&0x137).copied().unwrap_or(0);
+                let address = self.registers.get(&0x130).copied().unwrap_or(0);
+                let rx = usize::from(config & 0x08 == 0);
+                if let Some(data) = self.gain_tables.get(&(rx, address)) {
+                    words[2] = data[usize::from(register - 0x134)];
+                }
+            }
+            // Product ID
+            if register == 0x37 {
+                words[2] = 0xA; // Rev[2:0] = 2
+            }
+            // BBPLL register
+            if register == 0x0A && !self.registers.contains_key(&0x0A) {
+                words[2] = 3; // default
+            }
+            // Temperature
+            if register == 0xe {
+                words[2] = 3;
+            }
+            // BB Cal register
+            if register == 0x16 {
+                words[2] = 0; // BB Cal always completes immediately
+            }
+            // Overflow register
+            if register == 0x5e {
+                words[2] = 0x80; // BBPLL always locks
+            }
+            // RxBBF
+            if register == 0x1e6 {
+                words[2] = 1; // default
+            }
+            if register == 0x1e8 || register == 0x1ea || register == 0x1ec {
+                words[2] = 0x60; // default
+            }
+            // Rx Synth / Tx Synth
+            if register == 0x244 || register == 0x284 {
+                words[2] = 0xC0; // CP Cal is always valid and done
+            }
+            if register == 0x247 || register == 0x287 {
+                words[2] = 0x02; // PLL always locks
+            }
+        }
+    }
+    #[cfg(not(feature = "eh1"))]
+    impl blocking::spi::Transfer<u8> for DummySPI {
+        type Error = ();
+
+        fn transfer<'w>(
+            &mut self,
+            words: &'w mut [u8],
+        ) -> Result<&'w [u8], Self::Error> {
+            self.transfer_bytes(words);
+            Ok(words)
+        }
+    }
+    #[cfg(feature = "eh1")]
+    impl spi::ErrorType for DummySPI {
+        type Error = core::convert::Infallible;
+    }
+    #[cfg(feature = "eh1")]
+    impl spi::SpiDevice for DummySPI {
+        fn transaction(
+            &mut self,
+            operations: &mut [spi::Operation<'_, u8>],
+        ) -> Result<(), Self::Error> {
+            for operation in operations {
+                match operation {
+                    spi::Operation::TransferInPlace(words) => {
+                        self.transfer_bytes(words)
+                    }
+                    _ => unimplemented!("only in-place transfers are used"),
+                }
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn struct_size() {
+        let size = core::mem::size_of::<Ad9361InitParam>();
+        println!("Ad9361InitParam {} bytes", size);
+        assert!(size < 1024, "Ad9361 Init Param size has grown!");
+
+        let size = core::mem::size_of::<
+            Ad9361<DummySPI, DummyResetB, TestDelay, DummyResetB>,
+        >();
+        println!("Ad9361 {} bytes", size);
+        // Not counting the shadow copy of the register space
+        #[cfg(feature = "register_cache")]
+        let size = size - core::mem::size_of::<interop::RegisterCache>();
+        assert!(size < 1024, "Ad9361 size has grown!");
+    }
+
+    fn test_setup() -> (
+        Ad9361InitParam,
+        DummySPI,
+        TestDelay,
+        DummyResetB,
+        DummyResetB,
+        Vec<u32>,
+    ) {
+        env_logger::try_init().ok();
+
+        let parameters: Ad9361InitParam = Default::default();
+        let spi: DummySPI = Default::default();
+        let resetb: DummyResetB = Default::default();
+        let sync: DummyResetB = Default::default();
+        let delay = TestDelay {};
+        let heap = Vec::with_capacity(MIN_HEAP_WORDS);
+
+        (parameters, spi, delay, resetb, sync, heap)
+    }
+
+    /// Basic initialisation
+    #[test]
+    #[serial]
+    fn init() {
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
+        ad9361.init(parameters).unwrap();
+    }
+
+    /// Software reset (no dedicated reset pin)
+    #[test]
+    #[serial]
+    fn software_reset() {
+        let (parameters, spi, delay, _, _, heap) = test_setup();
+
+        let mut ad9361 = Ad9361::new_no_reset(spi, delay, heap);
+        ad9361.init(parameters).unwrap();
+    }
+
+    /// Re-initialise
+    #[test]
+    #[serial]
+    fn reinit() {
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+
+        let mut ad9361: Ad9361<_, _, DummyResetB, DummyResetB> =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
+        ad9361.init(parameters).unwrap();
+
+        let parameters: Ad9361InitParam = Default::default();
+        ad9361.init(parameters).unwrap(); // and again
+    }
+
+    /// Reset with the RESETB pin and over SPI, then re-initialise
+    #[test]
+    #[serial]
+    fn reset() {
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut ad9361: Ad9361<_, _, DummyResetB, DummyResetB> =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
+        ad9361.init(parameters).unwrap();
+
+        ad9361.reset().unwrap();
+        ad9361.init(Default::default()).unwrap();
+
+        let (parameters, spi, delay, _, _, heap) = test_setup();
+        let mut ad9361: Ad9361<_, _, DummyResetB, DummyResetB> =
+            Ad9361::new(spi, delay, None, None, heap);
+        ad9361.init(parameters).unwrap();
+
+        ad9361.reset().unwrap();
+        ad9361.init(Default::default()).unwrap();
+    }
+
+    /// Debug output before and after initialisation
+    #[test]
+    #[serial]
+    fn debug_format() {
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut ad9361: Ad9361<_, _, DummyResetB, DummyResetB> =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
+        assert_eq!(format!("{:?}", ad9361), "Ad9361 { uninitialized }");
+
+        ad9361.init(parameters).unwrap();
+        let debug = format!("{:?}", ad9361);
+        info!("{}", debug);
+        assert!(debug.contains("ensm_state: Ok("));
+        assert!(debug.contains("rx_lo_freq: Ok("));
+    }
+
+    /// Initialise with a separate chip select pin
+    #[test]
+    #[serial]
+    fn chip_select() {
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let cs: DummyResetB = Default::default();
+        let spi = hal::SpiCs::new(spi, cs).unwrap();
+
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
+        ad9361.init(parameters).unwrap();
+        ad9361.get_temperature().unwrap();
+    }
+
+    /// Reads of written registers are answered from the cache
+    #[cfg(feature = "register_cache")]
+    #[test]
+    #[serial]
+    fn register_cache() {
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
+        ad9361.init(parameters).unwrap();
+
+        ad9361.spi_write(0x03C, 0x21).expect("Failed to write");
+        // Change the register behind the driver's back
+        ad9361.inner_spi().registers.insert(0x03C, 0x00);
+        assert_eq!(ad9361.spi_read(0x03C), Ok(0x21));
+
+        ad9361.invalidate_cache();
+        assert_eq!(ad9361.spi_read(0x03C), Ok(0x00));
+    }
+
+    /// Coalesce register writes during initialisation
+    #[test]
+    #[serial]
+    fn init_write_coalescing() {
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
+        ad9361.init(parameters).unwrap();
+        let transfers = ad9361.inner_spi().transfers;
+        let registers = ad9361.inner_spi().registers.clone();
+
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
+        ad9361.set_init_write_coalescing(true);
+        ad9361.init(parameters).unwrap();
+
+        info!(
+            "{} -> {} transfers",
+            transfers,
+            ad9361.inner_spi().transfers
+        );
+        assert!(ad9361.inner_spi().transfers < transfers);
+        assert_eq!(ad9361.inner_spi().registers, registers);
+    }
+
+    /// Force the ENSM state
+    #[test]
+    #[serial]
+    fn ensm_force_state() {
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
+        ad9361.init(parameters).unwrap();
+
+        ad9361.ensm_force_state(EnsmState::Alert).unwrap();
+        assert_eq!(
+            ad9361.ensm_force_state(EnsmState::TxFlush),
+            Err(Ad9361Error::InvalidArgument)
+        );
+    }
+
+    /// Move an Ad9361 to another thread before initialisation
+    #[test]
+    #[serial]
+    fn send_to_thread() {
+        let (_, spi, delay, resetb, sync, heap) = test_setup();
+        let ad9361 = Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
+        let ad9361 = unsafe { ad9361.into_send() };
+
+        std::thread::spawn(move || {
+            let mut ad9361 = ad9361.into_inner();
+            ad9361.init(Default::default()).unwrap();
+        })
+        .join()
+        .unwrap();
+    }
+
+    /// Wait for the ENSM to reach a state
+    #[test]
+    #[serial]
+    fn wait_for_ensm_state() {
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
+        ad9361.init(parameters).unwrap();
+
+        ad9361.ensm_force_state(EnsmState::Alert).unwrap();
+        ad9361.wait_for_ensm_state(EnsmState::Alert, 10).unwrap();
+        assert_eq!(
+            ad9361.wait_for_ensm_state(EnsmState::Tx, 1),
+            Err(Ad9361Error::TimedOut)
+        );
+    }
+
+    /// Force the ENSM state within a scope
+    #[test]
+    #[serial]
+    fn force_state_scoped() {
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
+        ad9361.init(parameters).unwrap();
+
+        ad9361.ensm_force_state(EnsmState::Fdd).unwrap();
+        let previous = ad9361.ensm_get_state();
+        assert_eq!(previous, EnsmState::Fdd);
+        {
+            let mut guard =
+                ad9361.force_state_scoped(EnsmState::Alert).unwrap();
+            assert_eq!(guard.previous(), previous);
+            assert_eq!(guard.ensm_get_state(), EnsmState::Alert);
+            guard.spi_write(0x03C, 0x21).expect("Failed to write");
+        }
+        assert_eq!(ad9361.spi_read(0x03C), Ok(0x21));
+        assert_eq!(ad9361.ensm_get_state(), previous);
+    }
+
+    /// Status registers are read from the device, even when cached
+    #[cfg(feature = "register_cache")]
+    #[test]
+    #[serial]
+    fn register_cache_status() {
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
+        ad9361.init(parameters).unwrap();
+
+        // Clear an overflow flag, which leaves the BBPLL lock bit at zero in
+        // the value written
+        ad9361.spi_write(0x05E, 0x01).expect("Failed to write");
+        let faults = ad9361.read_and_clear_faults().unwrap();
+        assert!(!faults.contains(FaultFlags::BBPLL_UNLOCKED));
+
+        // Ordinary registers are still cached
+        ad9361.spi_write(0x03C, 0x21).expect("Failed to write");
+        assert_eq!(ad9361.spi_read(0x03C), Ok(0x21));
+    }
+
+    /// Two instances, as on a multi-chip board
+    #[test]
+    #[serial]
+    fn two_instances() {
+        let (parameters, spi, delay, _, _, heap) = test_setup();
+        let mut first: Ad9361<_, _, DummyResetB, DummyResetB> =
+            Ad9361::new(spi, delay, None, None, heap);
+        first.init(parameters).unwrap();
+
+        let (parameters, spi, delay, _, _, heap) = test_setup();
+        let mut second: Ad9361<_, _, DummyResetB, DummyResetB> =
+            Ad9361::new(spi, delay, None, None, heap);
+        second.init(parameters).unwrap();
+
+        first.get_temperature().unwrap();
+        second.get_temperature().unwrap();
+
+        // While one instance is inside the driver the other is refused
+        {
+            let _active = first.activate().unwrap();
+            assert_eq!(second.get_temperature(), Err(Ad9361Error::Busy));
+            first.get_temperature().unwrap();
+        }
+        second.get_temperature().unwrap();
+
+        drop(first);
+        second.get_temperature().unwrap();
+    }
+
+    /// Allocate the heap on the stack
+    #[test]
+    #[serial]
+    fn static_heap() {
+        let (parameters, spi, delay, resetb, sync, _) = test_setup();
+        let mut heap: [u32; 540] = [0; 540];
+
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), &mut heap[..]);
+        ad9361.init(parameters).unwrap();
+    }
+
+    /// Pass a heap smaller than MIN_HEAP_WORDS, check for panic
+    #[test]
+    #[serial]
+    #[should_panic(expected = "MIN_HEAP_WORDS")]
+    fn small_heap() {
+        let (_parameters, spi, delay, resetb, sync, _) = test_setup();
+        let heap = Vec::with_capacity(400);
+
+        let _ = Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
+    }
+
+    /// Initialise with a heap smaller than MIN_HEAP_WORDS, check for
+    /// OutOfMemory
+    #[test]
+    #[serial]
+    fn overflow_heap() {
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
+        // Replace the heap after the debug assertion in new()
+        ad9361.heap = Vec::with_capacity(400).into();
+
+        assert_eq!(ad9361.init(parameters), Err(Ad9361Error::OutOfMemory));
+    }
+
+    /// Peak heap usage is within the heap
+    #[test]
+    #[serial]
+    fn heap_usage() {
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
+        ad9361.init(parameters).unwrap();
+
+        let usage = ad9361.heap_usage();
+        info!("Heap usage {} words", usage);
+        assert!(usage > 0);
+        assert!(usage <= MIN_HEAP_WORDS);
+    }
+
+    /// Heap array sized by MIN_HEAP_WORDS
+    #[test]
+    #[serial]
+    fn with_static_heap() {
+        let (parameters, spi, delay, resetb, sync, _) = test_setup();
+        let mut heap = [0u32; MIN_HEAP_WORDS];
+        let mut ad9361 = Ad9361::with_static_heap(
+            spi,
+            delay,
+            Some(resetb),
+            Some(sync),
+            &mut heap,
+        );
+        ad9361.init(parameters).unwrap();
+    }
+
+    /// Don't call init method, check for panic
+    #[test]
+    #[serial]
+    #[should_panic]
+    fn init_skipped() {
+        let (_parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let ad9361 = Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
+
+        let _ = ad9361
+            .get_temperature()
+            .expect("Failed to read temperature");
+    }
+
+    /// Configure the control outputs before init, check for panic
+    #[test]
+    #[serial]
+    #[should_panic(expected = "Must call init()")]
+    fn ctrl_outs_init_skipped() {
+        let (_parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
+
+        let _ = ad9361.configure_ctrl_outs(0, 0xFF);
+    }
+
+    /// Trigger each calibration on demand
+    #[test]
+    #[serial]
+    fn do_calib() {
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
+        ad9361.init(parameters).unwrap();
+
+        assert_eq!(ad9361.do_calib(CalibrationType::TxQuad, -1), Ok(()));
+        assert_eq!(ad9361.do_calib(CalibrationType::RfDc, -1), Ok(()));
+    }
+
+    /// Read the temperatures
+    #[test]
+    #[serial]
+    fn temperature() {
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
+        ad9361.init(parameters).unwrap();
+
+        info!("");
+        info!("Read temperature");
+        let t = ad9361
+            .get_temperature()
+            .expect("Failed to read temperature");
+        info!("T = {:.1}ºC", t);
+        info!("");
+
+        assert!((t - 2.6).abs() < 0.1);
+
+        let millicelsius = ad9361.get_temperature_millicelsius().unwrap();
+        assert_eq!(millicelsius as f32 / 1000., t);
+    }
+
+    /// Disable periodic temperature measurement
+    #[test]
+    #[serial]
+    fn temp_sense_periodic() {
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
+        ad9361.init(parameters).unwrap();
+
+        ad9361.set_temp_sense_periodic(false, 2000).unwrap();
+        let sense2 = ad9361.read_register(REG_TEMP_SENSE2).unwrap();
+        assert_eq!(sense2 & TEMP_SENSE_PERIODIC_ENABLE, 0);
+        assert_eq!(sense2 >> MEASUREMENT_TIME_INTERVAL_SHIFT, 4);
+        ad9361.get_temperature_millicelsius().unwrap();
+        assert_eq!(ad9361.read_register(REG_START_TEMP_READING), Ok(0));
+    }
+
+    /// Set the temperature sensor offset
+    #[test]
+    #[serial]
+    fn temp_sense_offset() {
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
+        ad9361.init(parameters).unwrap();
+
+        ad9361.set_temp_sense_offset(-20).unwrap();
+        assert_eq!(ad9361.read_register(REG_TEMP_OFFSET), Ok(-20i8 as u8));
+        assert_eq!(ad9361.params.0.temp_sense_offset_signed, -20);
+    }
+
+    /// Configure BIST mode for the receive path
+    #[test]
+    #[serial]
+    fn bist_prbs_rx() {
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
+        ad9361.init(parameters).unwrap();
+
+        info!("");
+        info!("Set PRBS");
+        ad9361
+            .bist_prbs(BistMode::InjectRx)
+            .expect("Failed to set BIST mode");
+    }
+
+    /// Configure a BIST tone and read it back
+    #[test]
+    #[serial]
+    fn bist_tone() {
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
+        ad9361.init(parameters).unwrap();
+
+        ad9361
+            .bist_tone(BistMode::InjectTx, 1_000_000, 6, 0)
+            .expect("Failed to set BIST tone");
+        let tone = ad9361.get_bist_tone().unwrap();
+        assert_eq!(
+            tone,
+            BistTone {
+                mode: BistMode::InjectTx,
+                frequency: 1_000_000,
+                level_db: 6,
+                mask: 0,
+                enabled: true,
+            }
+        );
+
+        ad9361
+            .bist_tone(BistMode::Disable, 0, 0, 0)
+            .expect("Failed to disable BIST tone");
+        assert!(!ad9361.get_bist_tone().unwrap().enabled);
+    }
+
+    /// Reconfigure the parallel data port
+    #[test]
+    #[serial]
+    fn digital_interface_mode() {
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
+        ad9361.init(parameters).unwrap();
+
+        let mut cfg = ad9361.get_digital_interface_mode().unwrap();
+        assert!(cfg.pp_tx_swap && cfg.rx_frame_pulse_mode);
+        cfg.swap_ports = true;
+        cfg.invert_data_clk = true;
+        ad9361.set_digital_interface_mode(cfg).unwrap();
+        assert_eq!(ad9361.get_digital_interface_mode(), Ok(cfg));
+        assert_eq!(ad9361.get_interface_electrical_mode(), Ok(true));
+        assert_eq!(ad9361.params.0.swap_ports_enable, 1);
+
+        ad9361.set_invert_rx_frame(true).unwrap();
+        let inverted = ad9361.get_digital_interface_mode().unwrap();
+        assert!(inverted.invert_rx_frame && inverted.invert_data_clk);
+        ad9361.set_invert_data_clk(false).unwrap();
+        let conf_1 = ad9361.read_register(REG_PARALLEL_PORT_CONF_1).unwrap();
+        assert_eq!(conf_1 & 1, 0);
+
+        // Single data rate and half duplex are not available in LVDS mode
+        assert_eq!(
+            ad9361.set_single_data_rate(true),
+            Err(Ad9361Error::InvalidArgument)
+        );
+        ad9361.set_interface_electrical_mode(false).unwrap();
+        ad9361.set_single_data_rate(true).unwrap();
+        assert!(
+            ad9361
+                .get_digital_interface_mode()
+                .unwrap()
+                .single_data_rate
+        );
+        ad9361.set_single_data_rate(false).unwrap();
+        ad9361.set_interface_electrical_mode(true).unwrap();
+
+        cfg.half_duplex = true;
+        assert_eq!(
+            ad9361.set_digital_interface_mode(cfg),
+            Err(Ad9361Error::InvalidArgument)
+        );
+    }
+
+    /// Reconfigure the parallel data port with a non-zero DELAY_RX_DATA
+    #[test]
+    #[serial]
+    fn digital_interface_mode_delay_rx_data() {
+        let (mut parameters, spi, delay, resetb, sync, heap) = test_setup();
+        parameters.set_delay_rx_data(2);
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
+        ad9361.init(parameters).unwrap();
+
+        let mut cfg = ad9361.get_digital_interface_mode().unwrap();
+        cfg.invert_rx_frame = !cfg.invert_rx_frame;
+        ad9361.set_digital_interface_mode(cfg).unwrap();
+
+        let conf_2 = ad9361.read_register(REG_PARALLEL_PORT_CONF_2).unwrap();
+        assert_eq!(conf_2 & DELAY_RX_DATA_MASK, 2);
+        let pp_conf = unsafe { (*(*ad9361.inner).pdata).port_ctrl.pp_conf };
+        assert_eq!(pp_conf[1] & DELAY_RX_DATA_MASK, 2);
+    }
+
+    /// Tune the digital interface delays
+    #[test]
+    #[serial]
+    fn dig_tune() {
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
+        ad9361.init(parameters).unwrap();
+        let rate = ad9361.get_rx_sampling_freq().unwrap();
+
+        // RX passes at data delays 3 to 7, TX passes at every delay
+        let mut n = 0;
+        let tuned = ad9361
+            .dig_tune(0, DigTuneFlags::BE_VERBOSE, |tx| {
+                let data_delay = n - 16;
+                n += 1;
+                if tx || (3..=7).contains(&data_delay) {
+                    PrbsResult::Locked { errors: 0 }
+                } else {
+                    PrbsResult::NeverLocked
+                }
+            })
+            .unwrap();
+        assert_eq!(n, 64);
+        assert_eq!(
+            tuned.rx,
+            Some(DigTuneDelay {
+                clock_delay: 0,
+                data_delay: 5,
+                window: 5
+            })
+        );
+        assert_eq!(tuned.tx.map(|tx| tx.window), Some(16));
+        assert_eq!(ad9361.read_register(REG_RX_CLOCK_DATA_DELAY), Ok(5));
+        assert_eq!(ad9361.read_register(REG_TX_CLOCK_DATA_DELAY), Ok(8 << 4));
+        assert!(!ad9361.is_tx_muted(TxChannel::Tx1));
+        assert_eq!(ad9361.get_rx_sampling_freq(), Ok(rate));
+
+        // No passing delays
+        assert_eq!(
+            ad9361.dig_tune(0, DigTuneFlags::empty(), |_| {
+                PrbsResult::NeverLocked
+            }),
+            Err(Ad9361Error::Io)
+        );
+        assert_eq!(
+            ad9361.dig_tune(0, DigTuneFlags::DO_IDELAY, |_| {
+                PrbsResult::NeverLocked
+            }),
+            Err(Ad9361Error::InvalidArgument)
+        );
+        assert!(!ad9361.is_tx_muted(TxChannel::Tx2));
+    }
+
+    /// PRBS test over the digital loopback
+    #[test]
+    #[serial]
+    fn prbs_loopback_test() {
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
+        ad9361.init(parameters).unwrap();
+
+        let result = ad9361
+            .run_prbs_loopback_test(10, || PrbsResult::Locked { errors: 0 })
+            .expect("Failed to run PRBS test");
+        assert!(result.passed());
+        assert!(!PrbsResult::NeverLocked.passed());
+        assert!(matches!(ad9361.get_bist_loopback(), LoopbackMode::Disabled));
+    }
+
+    /// Configure BIST mode for the transmit path
+    #[test]
+    #[serial]
+    fn bist_loopback_tx() {
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
+        ad9361.init(parameters).unwrap();
+
+        info!("");
+        info!("Set Loopback");
+        ad9361
+            .bist_loopback(LoopbackMode::Enabled)
+            .expect("Failed to set loopback mode");
+    }
+
+    /// Set the transmit attenuation value
+    #[test]
+    #[serial]
+    fn tx_attenuation() {
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
+        ad9361.init(parameters).unwrap();
+
+        info!("");
+        info!("Set Tx Gain Attenuation");
+        ad9361
+            .set_tx_attenuation(1, 10_000)
+            .expect("Failed to set Tx Gain Attenuation");
+        assert_eq!(ad9361.get_tx_attenuation_db(1), Ok(10.));
+    }
+
+    /// LO frequencies outside the device range are rejected
+    #[test]
+    #[serial]
+    fn lo_frequency_range() {
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
+        ad9361.init(parameters).unwrap();
+
+        let range = Ad9361::lo_frequency_range();
+        assert_eq!(
+            ad9361.set_rx_lo_freq(range.end() + 1),
+            Err(Ad9361Error::InvalidArgument)
+        );
+        assert_eq!(
+            ad9361.set_tx_lo_freq(range.start() - 1),
+            Err(Ad9361Error::InvalidArgument)
+        );
+    }
+
+    /// Change the CLK_OUT signal
+    #[test]
+    #[serial]
+    fn clk_output_mode() {
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
+        ad9361.init(parameters).unwrap();
+
+        let clock_enable = ad9361.spi_read(0x009).unwrap();
+        for mode in [
+            ClkOutputMode::AdcClkDiv8,
+            ClkOutputMode::BufferedXtalnDcxo,
+            ClkOutputMode::Disable,
+        ] {
+            ad9361.set_clk_output_mode(mode).unwrap();
+            assert_eq!(ad9361.get_clk_output_mode(), Ok(mode));
+            // The clock enables, including XO_BYPASS, are not changed
+            assert_eq!(ad9361.spi_read(0x009), Ok(clock_enable));
+        }
+    }
+
+    /// Defer attenuation changes to ALERT, across an attenuation change
+    #[test]
+    #[serial]
+    fn tx_atten_update_in_alert() {
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
+        ad9361.init(parameters).unwrap();
+
+        for enable in [true, false] {
+            ad9361.set_tx_atten_update_in_alert(enable).unwrap();
+            ad9361.set_tx_attenuation(TxChannel::Tx1, 10_000).unwrap();
+            assert_eq!(ad9361.get_tx_atten_update_in_alert(), Ok(enable));
+        }
+    }
+
+    /// Set the attenuation of both transmit channels at once
+    #[test]
+    #[serial]
+    fn tx_attenuation_both() {
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
+        ad9361.init(parameters).unwrap();
+
+        ad9361
+            .set_tx_attenuation_both(20_000, true, true)
+            .expect("Failed to set Tx Gain Attenuation");
+    }
+
+    /// Select an external RX LO
+    #[test]
+    #[serial]
+    fn rx_external_lo() {
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
+        ad9361.init(parameters).unwrap();
+
+        ad9361.set_rx_external_lo_freq(2_400_000_000).unwrap();
+        assert_eq!(ad9361.rx_lo_hz(), Ok(2_400_000_000));
+        assert_eq!(
+            ad9361.set_rx_external_lo_freq(10),
+            Err(Ad9361Error::InvalidArgument)
+        );
+
+        ad9361
+            .set_rx_lo_int_ext(InternalExternalLO::Internal)
+            .unwrap();
+        assert_eq!(ad9361.rx_lo_hz(), ad9361.get_rx_lo_freq());
+    }
+
+    /// Read the product ID and revision
+    #[test]
+    #[serial]
+    fn product_id() {
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
+        ad9361.init(parameters).unwrap();
+
+        assert_eq!(ad9361.product_id(), Ok(ProductId::Ad936x));
+        assert_eq!(ad9361.device_revision(), Ok(2));
+    }
+
+    /// Mute and unmute a transmit channel, restoring its attenuation
+    #[test]
+    #[serial]
+    fn tx_mute() {
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
+        ad9361.init(parameters).unwrap();
+
+        ad9361.set_tx_attenuation(TxChannel::Tx1, 10_000).unwrap();
+        assert_eq!(ad9361.tx_mute(TxChannel::Tx1, true), Ok(10_000));
+        assert!(ad9361.is_tx_muted(TxChannel::Tx1));
+        assert!(!ad9361.is_tx_muted(TxChannel::Tx2));
+        assert_eq!(ad9361.get_tx_attenuation(TxChannel::Tx1), Ok(89_750));
+
+        // Muting again keeps the saved attenuation
+        assert_eq!(ad9361.tx_mute(TxChannel::Tx1, true), Ok(89_750));
+
+        assert_eq!(ad9361.tx_mute(TxChannel::Tx1, false), Ok(89_750));
+        assert!(!ad9361.is_tx_muted(TxChannel::Tx1));
+        assert_eq!(ad9361.get_tx_attenuation(TxChannel::Tx1), Ok(10_000));
+    }
+
+    /// Power down the TX LO
+    #[test]
+    #[serial]
+    fn powerdown_tx_lo() {
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
+        ad9361.init(parameters).unwrap();
+
+        info!("");
+        info!("Powerdown TX LO");
+        ad9361
+            .tx_lo_powerdown(LOPowerStatus::Off)
+            .expect("Failed to powerdown TX LO");
+        assert_eq!(
+            ad9361
+                .get_tx_lo_power()
+                .expect("Failed to get power status of TX LO"),
+            LOPowerStatus::Off
+        );
+    }
+
+    /// Set the RX FIR configuration with the current path clocks
+    #[test]
+    #[serial]
+    fn apply_rx_fir() {
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
+        ad9361.init(parameters).unwrap();
+
+        ad9361
+            .apply_rx_fir(Ad9361RxFir::default())
+            .expect("Failed to apply RX FIR");
+        ad9361
+            .set_rx_fir_en_dis(true)
+            .expect("Failed to enable RX FIR");
+    }
+
+    /// Enable the TX FIR filter
+    #[test]
+    #[serial]
+    fn tx_fir_filter_enable() {
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
+        ad9361.init(parameters).unwrap();
+        let tx_fir = Ad9361TxFir::default();
+
+        // must first set a value config
+        ad9361.set_tx_fir_config(tx_fir).unwrap();
+
+        info!("");
+        info!("Enable TX FIR filter");
+        assert!(!ad9361.get_tx_fir_en_dis().expect("Failed to get FIR en"));
+        ad9361
+            .set_tx_fir_en_dis(true)
+            .expect("Failed to set FIR en");
+        assert!(ad9361.get_tx_fir_en_dis().expect("Failed to get FIR en"));
+    }
+
+    /// Disable RF and baseband DC offset tracking independently
+    #[test]
+    #[serial]
+    fn rx_dc_tracking_enable() {
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
+        ad9361.init(parameters).unwrap();
+
+        ad9361
+            .set_rx_rfdc_track_en_dis(false)
+            .expect("Failed to set RF DC tracking");
+        assert!(!ad9361
+            .get_rx_rfdc_track_en_dis()
+            .expect("Failed to get RF DC tracking"));
+        ad9361
+            .set_rx_bbdc_track_en_dis(true)
+            .expect("Failed to set BB DC tracking");
+        assert!(ad9361
+            .get_rx_bbdc_track_en_dis()
+            .expect("Failed to get BB DC tracking"));
+    }
+
+    /// Save a fastlock profile and load it back
+    #[test]
+    #[serial]
+    fn fastlock_save_load() {
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
+        ad9361.init(parameters).unwrap();
+
+        ad9361
+            .rx_fastlock_store(0)
+            .expect("Failed to store profile");
+        let values = ad9361.rx_fastlock_save(0).expect("Failed to save");
+        ad9361
+            .rx_fastlock_load(1, &values)
+            .expect("Failed to load profile");
+        assert_eq!(ad9361.rx_fastlock_save(1), Ok(values));
+
+        // Out of range
+        assert!(ad9361.tx_fastlock_recall(8).is_err());
+    }
+
+    /// Read the gain table entry applied to the RX front end
+    #[test]
+    #[serial]
+    fn rx_gain_bits() {
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
+        ad9361.init(parameters).unwrap();
+        // Gain Rx1: full table index
+        ad9361.inner_spi().registers.insert(0x2B0, 40);
+
+        let entry = ad9361.get_rx_gain_bits(0).expect("Failed to read gain");
+        let gain = ad9361.get_rx_gain(0).expect("Failed to read gain");
+        assert_eq!(i32::from(entry.abs_gain()), gain);
+
+        // The entry of the full table loaded for the RX LO frequency
+        let lo = ad9361.get_rx_lo_freq().unwrap();
+        let loaded = GainTable::new_from_recommended(GainTableKind::Full, lo)
+            .get_entry(40 + 1);
+        assert_eq!(entry.reg131(), loaded.reg131());
+        assert_eq!(entry.reg132(), loaded.reg132());
+        assert_eq!(entry.reg133(), loaded.reg133());
+
+        // Invalid channel
+        assert!(ad9361.get_rx_gain_bits(2).is_err());
+    }
+
+    /// Read the receive gain split into stages
+    #[test]
+    #[serial]
+    fn rx_gain_detailed() {
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
+        ad9361.init(parameters).unwrap();
+
+        let info = ad9361.get_rx_gain_detailed(RxChannel::Rx1).unwrap();
+        assert_eq!(info.gain_db, ad9361.get_rx_gain(0).unwrap());
+    }
+
+    /// Restart the RSSI measurement by SPI
+    #[test]
+    #[serial]
+    fn rssi_restart() {
+        let (mut parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
+        ad9361.init(parameters).unwrap();
+
+        // Default restart mode is on gain change
+        assert_eq!(ad9361.rssi_restart(), Err(Ad9361Error::InvalidArgument));
+
+        parameters.set_rssi_restart_mode(RSSI_MODE_SPI_WRITE);
+        ad9361.init(parameters).unwrap();
+        let before = ad9361.read_register(REG_RSSI_CONFIG).unwrap();
+        ad9361.rssi_restart().unwrap();
+        let config = ad9361.read_register(REG_RSSI_CONFIG).unwrap();
+        assert_eq!(config & START_RSSI_MEAS, 0);
+        // Only the start bit is pulsed
+        assert_eq!(config, before & !START_RSSI_MEAS);
+    }
 
-            // Product ID
-            if register == 0x37 {
-                words[2] = 0xA; // Rev[2:0] = 2
-            }
-            // BBPLL register
-            if register == 0x0A {
-                words[2] = 3; // default
-            }
-            // Temperature
-            if register == 0xe {
-                words[2] = 3;
-            }
-            // BB Cal register
-            if register == 0x16 {
-                words[2] = 0; // BB Cal always completes immediately
-            }
-            // Overflow register
-            if register == 0x5e {
-                words[2] = 0x80; // BBPLL always locks
-            }
-            // RxBBF
-            if register == 0x1e6 {
-                words[2] = 1; // default
-            }
-            if register == 0x1e8 || register == 0x1ea || register == 0x1ec {
-                words[2] = 0x60; // default
-            }
-            // Rx Synth / Tx Synth
-            if register == 0x244 || register == 0x284 {
-                words[2] = 0xC0; // CP Cal is always valid and done
-            }
-            if register == 0x247 || register == 0x287 {
-                words[2] = 0x02; // PLL always locks
-            }
+    /// Read the full RSSI measurement
+    #[test]
+    #[serial]
+    fn rx_rssi_raw() {
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
+        ad9361.init(parameters).unwrap();
 
-            Ok(words)
-        }
+        let rssi = ad9361.get_rx_rssi_raw(RxChannel::Rx1).unwrap();
+        assert_eq!(rssi.symbol_db(), ad9361.get_rx_rssi(0).unwrap());
     }
 
+    /// Channel arguments accept the typed channel enums
     #[test]
-    fn struct_size() {
-        let size = core::mem::size_of::<Ad9361InitParam>();
-        println!("Ad9361InitParam {} bytes", size);
-        assert!(size < 1024, "Ad9361 Init Param size has grown!");
+    #[serial]
+    fn typed_channels() {
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
+        ad9361.init(parameters).unwrap();
 
-        let size = core::mem::size_of::<
-            Ad9361<DummySPI, DummyResetB, linux_embedded_hal::Delay>,
-        >();
-        println!("Ad9361 {} bytes", size);
-        assert!(size < 1024, "Ad9361 size has grown!");
+        assert_eq!(
+            ad9361.get_rx_gain(RxChannel::Rx1).unwrap(),
+            ad9361.get_rx_gain(0).unwrap()
+        );
+        ad9361
+            .set_tx_attenuation(TxChannel::Tx1, 10_000)
+            .expect("Failed to set Tx Gain Attenuation");
     }
 
-    fn test_setup() -> (
-        Ad9361InitParam,
-        DummySPI,
-        linux_embedded_hal::Delay,
-        DummyResetB,
-        Vec<u32>,
-    ) {
-        env_logger::try_init().ok();
+    /// Adjust the DCXO tuning
+    #[test]
+    #[serial]
+    fn dcxo_tune() {
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
+        ad9361.init(parameters).unwrap();
 
-        let parameters: Ad9361InitParam = Default::default();
-        let spi: DummySPI = Default::default();
-        let resetb: DummyResetB = Default::default();
-        let delay = linux_embedded_hal::Delay {};
-        let heap = Vec::with_capacity(540);
+        assert_eq!(ad9361.get_dcxo_tune_coarse(), Ok(8));
+        ad9361
+            .set_dcxo_tune_fine(6000)
+            .expect("Failed to set DCXO fine tune");
+        assert_eq!(ad9361.get_dcxo_tune_fine(), Ok(6000));
 
-        (parameters, spi, delay, resetb, heap)
+        // External reference clock
+        ad9361.params.0.xo_disable_use_ext_refclk_enable = 1;
+        assert_eq!(
+            ad9361.set_dcxo_tune_coarse(8),
+            Err(Ad9361Error::InvalidArgument)
+        );
+        assert_eq!(
+            ad9361.get_dcxo_tune_fine(),
+            Err(Ad9361Error::InvalidArgument)
+        );
     }
 
-    /// Basic initialisation
+    /// Switch between 2R2T and 1R1T
     #[test]
     #[serial]
-    fn init() {
-        let (parameters, spi, delay, resetb, heap) = test_setup();
+    fn channel_mode() {
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
+        ad9361.init(parameters).unwrap();
+
+        ad9361
+            .set_no_ch_mode(ChannelMode::OneByOne)
+            .expect("Failed to set 1R1T");
+        ad9361
+            .set_no_ch_mode(ChannelMode::TwoByTwo)
+            .expect("Failed to set 2R2T");
+    }
 
-        let mut ad9361 = Ad9361::new(spi, delay, Some(resetb), heap);
+    /// Read the operating mode summary
+    #[test]
+    #[serial]
+    fn operating_mode() {
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
         ad9361.init(parameters).unwrap();
+
+        let mode = ad9361.operating_mode().expect("Failed to read mode");
+        info!("{}", mode);
+        assert_eq!(mode.duplex, DuplexMode::Fdd);
+        assert_eq!(mode.channels, ChannelMode::TwoByTwo);
+        assert_eq!(mode.gain_control[0], RfGainControlMode::SlowAttackAgc);
     }
 
-    /// Software reset (no dedicated reset pin)
+    /// Set the AuxDAC outputs
     #[test]
     #[serial]
-    fn software_reset() {
-        let (parameters, spi, delay, _, heap) = test_setup();
+    fn auxdac() {
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
+        ad9361.init(parameters).unwrap();
+
+        ad9361.set_auxdac(AuxDac::Dac1, 1500).unwrap();
+        assert_eq!(ad9361.get_auxdac(AuxDac::Dac1), Ok(1500));
+        assert_eq!(
+            ad9361.set_auxdac(AuxDac::Dac2, 100),
+            Err(Ad9361Error::InvalidArgument)
+        );
+    }
 
-        let mut ad9361: Ad9361<_, _, DummyResetB> =
-            Ad9361::new(spi, delay, None, heap);
+    /// Set the GPO pins in manual mode
+    #[test]
+    #[serial]
+    fn gpo() {
+        let (mut parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
+        parameters.set_gpo_manual_mode_enable(1);
         ad9361.init(parameters).unwrap();
+
+        ad9361.set_gpo(2, true).unwrap();
+        assert_eq!(ad9361.get_gpo(2), Ok(true));
+        assert_eq!(ad9361.get_gpo(1), Ok(false));
+        ad9361.set_gpo(2, false).unwrap();
+        assert_eq!(ad9361.get_gpo(2), Ok(false));
+        assert_eq!(ad9361.set_gpo(4, true), Err(Ad9361Error::InvalidArgument));
+
+        // Not in manual mode
+        let lna_ctrl = ad9361.spi_read(0x026).unwrap();
+        ad9361.spi_write(0x026, lna_ctrl & !0x10).unwrap();
+        assert_eq!(ad9361.set_gpo(2, true), Err(Ad9361Error::InvalidArgument));
     }
 
-    /// Re-initialise
+    /// Select the CTRL_OUT signal group
     #[test]
     #[serial]
-    fn reinit() {
-        let (parameters, spi, delay, resetb, heap) = test_setup();
+    fn ctrl_outs() {
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
+        ad9361.init(parameters).unwrap();
 
-        let mut ad9361: Ad9361<_, _, DummyResetB> =
-            Ad9361::new(spi, delay, Some(resetb), heap);
+        assert_eq!(ad9361.get_ctrl_outs(), Ok((0, 0xFF)));
+        ad9361.configure_ctrl_outs(0x16, 0x03).unwrap();
+        assert_eq!(ad9361.get_ctrl_outs(), Ok((0x16, 0x03)));
+    }
+
+    /// Measure the AUXADC pin, leaving the AuxADC powered down
+    #[test]
+    #[serial]
+    fn measure_aux_adc() {
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
         ad9361.init(parameters).unwrap();
 
-        let parameters: Ad9361InitParam = Default::default();
-        ad9361.init(parameters).unwrap(); // and again
+        ad9361
+            .spi_write(REG_AUXADC_CONFIG, AUX_ADC_POWER_DOWN)
+            .unwrap();
+        ad9361.spi_write(REG_AUXADC_WORD_MSB, 0x80).unwrap();
+        ad9361.spi_write(REG_AUXADC_WORD_LSB, 0x00).unwrap();
+
+        assert_eq!(ad9361.get_auxadc(), Ok(0x800));
+        assert_eq!(ad9361.get_auxadc_mv(), Ok(650));
+        assert_eq!(ad9361.get_aux_adc_source(), Ok(None));
     }
 
-    /// Allocate the heap on the stack
+    /// Toggle the ENSM return to Alert
     #[test]
     #[serial]
-    fn static_heap() {
-        let (parameters, spi, delay, resetb, _) = test_setup();
-        let mut heap: [u32; 540] = [0; 540];
+    fn ensm_auto_return() {
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
+        ad9361.init(parameters).unwrap();
+
+        assert_eq!(ad9361.get_ensm_auto_return(), Ok(true));
+        ad9361.set_ensm_auto_return(false).unwrap();
+        assert_eq!(ad9361.get_ensm_auto_return(), Ok(false));
+        ad9361.set_ensm_auto_return(true).unwrap();
+        assert_eq!(ad9361.get_ensm_auto_return(), Ok(true));
+    }
+
+    /// Read the TX monitor of both channels
+    #[test]
+    #[serial]
+    fn tx_monitor() {
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
+        ad9361.init(parameters).unwrap();
+
+        // TX RSSI1, TX RSSI2 and TX RSSI LSB
+        ad9361.inner_spi().registers.insert(0x06B, 0x40);
+        ad9361.inner_spi().registers.insert(0x06C, 0x20);
+        ad9361.inner_spi().registers.insert(0x06D, 0x02);
+
+        let tx1 = ad9361.get_tx_monitor(0).unwrap();
+        assert_eq!(tx1, TxMonitor { rssi: 0x80 });
+        assert_eq!(tx1.rssi_mdb(), 32_000);
+        let tx2 = ad9361.get_tx_monitor(TxChannel::Tx2).unwrap();
+        assert_eq!(tx2, TxMonitor { rssi: 0x41 });
+        assert_eq!(tx2.rssi_mdb(), 16_250);
+        assert_eq!(tx2.rssi_db(), 16.25);
 
-        let mut ad9361 = Ad9361::new(spi, delay, Some(resetb), &mut heap[..]);
+        // Invalid channel
+        assert_eq!(ad9361.get_tx_monitor(2), Err(Ad9361Error::InvalidArgument));
+    }
+
+    /// Adjust the bias trims
+    #[test]
+    #[serial]
+    fn bias_trims() {
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
         ad9361.init(parameters).unwrap();
+
+        assert_eq!(ad9361.get_master_bias_trim(), Ok(0x0E));
+        assert_eq!(ad9361.get_bandgap_temp_trim(), Ok(0x0E));
+        ad9361.set_master_bias_trim(0x10).unwrap();
+        assert_eq!(ad9361.get_master_bias_trim(), Ok(0x10));
+        assert_eq!(ad9361.spi_read(0x2A6), Ok(0x10));
+        ad9361.set_bandgap_temp_trim(0x1F).unwrap();
+        assert_eq!(ad9361.get_bandgap_temp_trim(), Ok(0x1F));
+        assert_eq!(ad9361.spi_read(0x2A8), Ok(0x1F));
+        assert_eq!(
+            ad9361.set_master_bias_trim(32),
+            Err(Ad9361Error::InvalidArgument)
+        );
+        assert_eq!(
+            ad9361.set_bandgap_temp_trim(32),
+            Err(Ad9361Error::InvalidArgument)
+        );
     }
 
-    /// Overflow the heap, check for panic
+    /// Adjust the RF DC offset attenuation
     #[test]
     #[serial]
-    #[should_panic]
-    fn overflow_heap() {
-        let (parameters, spi, delay, resetb, _) = test_setup();
-        let heap = Vec::with_capacity(400);
+    fn rf_dc_offset_attenuation() {
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
+        ad9361.init(parameters).unwrap();
+
+        assert_eq!(ad9361.get_rf_dc_offset_attenuation(), Ok((6, 5)));
+        ad9361.set_rf_dc_offset_attenuation(20, 10).unwrap();
+        assert_eq!(ad9361.get_rf_dc_offset_attenuation(), Ok((20, 10)));
+        // The RX LO is below 4 GHz, so the low range setting applies
+        assert_eq!(ad9361.spi_read(0x188), Ok(10));
+        assert_eq!(
+            ad9361.set_rf_dc_offset_attenuation(32, 10),
+            Err(Ad9361Error::InvalidArgument)
+        );
+        assert_eq!(ad9361.get_rf_dc_offset_attenuation(), Ok((20, 10)));
+    }
 
-        let mut ad9361 = Ad9361::new(spi, delay, Some(resetb), heap);
+    /// Capture a profile, change settings, and restore it
+    #[test]
+    #[serial]
+    fn profile() {
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
         ad9361.init(parameters).unwrap();
+
+        let profile = ad9361.capture_profile().unwrap();
+        ad9361.set_rx_lo_freq(915_000_000).unwrap();
+        ad9361.set_tx_attenuation(TxChannel::Tx2, 30_000).unwrap();
+        assert_ne!(ad9361.capture_profile(), Ok(profile));
+
+        ad9361.apply_profile(&profile).unwrap();
+        assert_eq!(ad9361.capture_profile(), Ok(profile));
     }
 
-    /// Don't call init method, check for panic
+    /// Connect the calibration switch pins
     #[test]
     #[serial]
-    #[should_panic]
-    fn init_skipped() {
-        let (_parameters, spi, delay, resetb, heap) = test_setup();
-        let ad9361 = Ad9361::new(spi, delay, Some(resetb), heap);
+    fn cal_switches() {
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut cal_sw1 = DummyResetB::default();
+        let mut cal_sw2 = DummyResetB::default();
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap)
+                .with_cal_switches(&mut cal_sw1, &mut cal_sw2);
+        ad9361.init(parameters).unwrap();
 
-        let _ = ad9361
-            .get_temperature()
-            .expect("Failed to read temperature");
+        assert_eq!(ad9361.params.0.gpio_cal_sw1.number, 3);
+        assert_eq!(ad9361.params.0.gpio_cal_sw2.number, 4);
+        assert!(!ad9361.params.0.gpio_cal_sw2.extra.is_null());
     }
 
-    /// Read the temperatures
+    /// Snapshot the parameters in effect, and initialise with them
     #[test]
     #[serial]
-    fn temperature() {
-        let (parameters, spi, delay, resetb, heap) = test_setup();
-        let mut ad9361 = Ad9361::new(spi, delay, Some(resetb), heap);
+    fn effective_params() {
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
         ad9361.init(parameters).unwrap();
+        ad9361.set_rx_rf_bandwidth(10_000_000).unwrap();
+        ad9361.set_tx_attenuation(TxChannel::Tx1, 20_000).unwrap();
 
-        info!("");
-        info!("Read temperature");
-        let t = ad9361
-            .get_temperature()
-            .expect("Failed to read temperature");
-        info!("T = {:.1}ºC", t);
-        info!("");
+        let params = ad9361.effective_params().unwrap();
+        assert_eq!(
+            params.rf_rx_bandwidth_hz(),
+            ad9361.get_rx_rf_bandwidth().unwrap()
+        );
+        assert_eq!(params.0.tx_attenuation_mdB, 20_000);
+        assert_eq!(params.0.gpio_resetb.number, -1);
 
-        assert!((t - 2.6).abs() < 0.1);
+        ad9361.init(params).unwrap();
+        assert_eq!(ad9361.get_tx_attenuation(TxChannel::Tx1), Ok(20_000));
     }
 
-    /// Configure BIST mode for the receive path
+    /// Read back the default clock tree
     #[test]
     #[serial]
-    fn bist_prbs_rx() {
-        let (parameters, spi, delay, resetb, heap) = test_setup();
-        let mut ad9361 = Ad9361::new(spi, delay, Some(resetb), heap);
+    fn clock_frequencies() {
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
         ad9361.init(parameters).unwrap();
 
-        info!("");
-        info!("Set PRBS");
-        ad9361
-            .bist_prbs(BistMode::InjectRx)
-            .expect("Failed to set BIST mode");
+        let clocks = ad9361
+            .get_clock_frequencies()
+            .expect("Failed to get clock frequencies");
+        info!("{:?}", clocks);
+        assert_eq!(clocks.rx_sample, ad9361.get_rx_sampling_freq().unwrap());
+        assert_eq!(clocks.tx_sample, ad9361.get_tx_sampling_freq().unwrap());
     }
 
-    /// Configure BIST mode for the transmit path
+    /// Set the whole clock tree
     #[test]
     #[serial]
-    fn bist_loopback_tx() {
-        let (parameters, spi, delay, resetb, heap) = test_setup();
-        let mut ad9361 = Ad9361::new(spi, delay, Some(resetb), heap);
+    fn trx_path_clks() {
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
         ad9361.init(parameters).unwrap();
 
-        info!("");
-        info!("Set Loopback");
-        ad9361
-            .bist_loopback(LoopbackMode::Enabled)
-            .expect("Failed to set loopback mode");
+        // No FIR decimation or interpolation
+        let rx = [983040000, 122880000, 61440000, 30720000, 15360000, 15360000];
+        let tx = [983040000, 61440000, 61440000, 30720000, 15360000, 15360000];
+        ad9361.set_trx_path_clks(&rx, &tx).unwrap();
+        assert_eq!(ad9361.get_trx_path_clks(), Ok((rx, tx)));
+        assert_eq!(ad9361.get_rx_sampling_freq(), Ok(15360000));
+        assert_eq!(ad9361.get_tx_sampling_freq(), Ok(15360000));
     }
 
-    /// Set the transmit attenuation value
+    /// Self test with the dummy SPI
     #[test]
     #[serial]
-    fn tx_attenuation() {
-        let (parameters, spi, delay, resetb, heap) = test_setup();
-        let mut ad9361 = Ad9361::new(spi, delay, Some(resetb), heap);
+    fn self_test() {
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
         ad9361.init(parameters).unwrap();
 
-        info!("");
-        info!("Set Tx Gain Attenuation");
-        ad9361
-            .set_tx_attenuation(1, 10_000)
-            .expect("Failed to set Tx Gain Attenuation");
+        let report = ad9361.self_test().expect("Failed to run self test");
+        info!("{}", report);
+        assert!(report.product_id);
+        assert!(report.spi_readback);
     }
 
-    /// Power down the TX LO
+    /// Raw register access
     #[test]
     #[serial]
-    fn powerdown_tx_lo() {
-        let (parameters, spi, delay, resetb, heap) = test_setup();
-        let mut ad9361 = Ad9361::new(spi, delay, Some(resetb), heap);
+    fn register_access() {
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
         ad9361.init(parameters).unwrap();
 
-        info!("");
-        info!("Powerdown TX LO");
-        ad9361
-            .tx_lo_powerdown(LOPowerStatus::Off)
-            .expect("Failed to powerdown TX LO");
+        ad9361.spi_write(0x03C, 0x21).expect("Failed to write");
+        assert_eq!(ad9361.spi_read(0x03C), Ok(0x21));
+
+        let mut values = [0; 9];
         assert_eq!(
-            ad9361
-                .get_tx_lo_power()
-                .expect("Failed to get power status of TX LO"),
-            LOPowerStatus::Off
+            ad9361.spi_read_multiple(0x03C, &mut values),
+            Err(Ad9361Error::InvalidArgument)
         );
     }
 
-    /// Enable the TX FIR filter
+    /// Read back FIR configuration for an invalid channel
     #[test]
     #[serial]
-    fn tx_fir_filter_enable() {
-        let (parameters, spi, delay, resetb, heap) = test_setup();
-        let mut ad9361 = Ad9361::new(spi, delay, Some(resetb), heap);
+    fn fir_config_invalid_channel() {
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
         ad9361.init(parameters).unwrap();
-        let tx_fir = Ad9361TxFir::default();
 
-        // must first set a value config
-        ad9361.set_tx_fir_config(tx_fir).unwrap();
+        assert!(matches!(
+            ad9361.get_rx_fir_config(2),
+            Err(Ad9361Error::InvalidArgument)
+        ));
+    }
 
-        info!("");
-        info!("Enable TX FIR filter");
-        assert!(!ad9361.get_tx_fir_en_dis().expect("Failed to get FIR en"));
-        ad9361
-            .set_tx_fir_en_dis(true)
-            .expect("Failed to set FIR en");
-        assert!(ad9361.get_tx_fir_en_dis().expect("Failed to get FIR en"));
+    /// Read back FIR configuration before one is loaded
+    #[test]
+    #[serial]
+    fn fir_config_not_loaded() {
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
+        ad9361.init(parameters).unwrap();
+        // Empty coefficient RAM: TX and RX filter coefficient read data
+        for register in [0x063, 0x064, 0x0F3, 0x0F4] {
+            ad9361.inner_spi().registers.insert(register, 0);
+        }
+
+        assert!(matches!(ad9361.get_tx_fir_config(0), Ok(None)));
+        assert!(matches!(ad9361.get_rx_fir_config(1), Ok(None)));
+    }
+
+    /// The SYNC pin is wired into the gpio_sync descriptor
+    #[test]
+    #[serial]
+    fn sync_pin() {
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
+        ad9361.init(parameters).unwrap();
+
+        assert!(ad9361.params.0.gpio_sync.number >= 0);
+        assert!(!ad9361.params.0.gpio_sync.extra.is_null());
+
+        // Without a SYNC pin the descriptor is left unconnected
+        drop(ad9361);
+        let (parameters, spi, delay, resetb, _, heap) = test_setup();
+        let mut ad9361: Ad9361<_, _, _, DummyResetB> =
+            Ad9361::new(spi, delay, Some(resetb), None, heap);
+        ad9361.init(parameters).unwrap();
+
+        assert_eq!(ad9361.params.0.gpio_sync.number, -1);
     }
 
     /// Set the BBPLL and calculate Rx/Tx chain clocks
     #[test]
     #[serial]
     fn set_sampling_rate() {
-        let (parameters, spi, delay, resetb, heap) = test_setup();
-        let mut ad9361 = Ad9361::new(spi, delay, Some(resetb), heap);
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
         ad9361.init(parameters).unwrap();
 
         info!("");
@@ -693,12 +5457,49 @@ mod tests {
             .expect("Failed to set BB sampling rate");
     }
 
+    /// Set the sampling rate and read back the achieved rate
+    #[test]
+    #[serial]
+    fn set_sampling_rate_checked() {
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
+        ad9361.init(parameters).unwrap();
+
+        let achieved = ad9361
+            .set_rx_sampling_freq_checked(4_000_000)
+            .expect("Failed to set BB sampling rate");
+        info!("Achieved {} Hz", achieved);
+        assert_eq!(achieved, ad9361.get_rx_sampling_freq().unwrap());
+    }
+
+    /// Set the RF bandwidth and read back the achieved value
+    #[test]
+    #[serial]
+    fn set_rf_bandwidth_checked() {
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
+        ad9361.init(parameters).unwrap();
+
+        let achieved = ad9361
+            .set_rx_rf_bandwidth_checked(5_000_000)
+            .expect("Failed to set RX RF bandwidth");
+        info!("Achieved {} Hz", achieved);
+        // The tune divider rounds up, so the bandwidth is at or below the
+        // request, within one divider step
+        assert!(achieved <= 5_000_000);
+        assert!(achieved > 4_900_000);
+        assert_eq!(ad9361.get_rx_rf_bandwidth(), Ok(5_000_000));
+    }
+
     /// Set the Rx and Tx Ports
     #[test]
     #[serial]
     fn set_rf_port_output() {
-        let (parameters, spi, delay, resetb, heap) = test_setup();
-        let mut ad9361 = Ad9361::new(spi, delay, Some(resetb), heap);
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
         ad9361.init(parameters).unwrap();
 
         info!("");
@@ -715,11 +5516,12 @@ mod tests {
     #[test]
     #[serial]
     fn set_full_gain_table() {
-        let (parameters, spi, delay, resetb, heap) = test_setup();
-        let mut ad9361 = Ad9361::new(spi, delay, Some(resetb), heap);
-        ad9361.init(parameters).unwrap();
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
         let mut gt =
             GainTable::new_from_recommended(GainTableKind::Full, 2_000_000_000);
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
+        ad9361.init(parameters).unwrap();
 
         info!("");
         info!("Set Full Gain Table");
@@ -728,17 +5530,75 @@ mod tests {
             .expect("Failed to set full gain table");
     }
 
+    /// Load a Full Gain Table for RX1
+    #[test]
+    #[serial]
+    fn load_full_gain_table() {
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let recommended =
+            GainTable::new_from_recommended(GainTableKind::Full, 2_000_000_000);
+        let mut gt = recommended;
+        for index in 1..=77 {
+            gt.set_entry(index, recommended.get_entry(index));
+        }
+        assert_eq!(gt.max_index(), 77);
+        // Each call borrows its table for the lifetime of ad9361
+        let mut rejected = gt;
+
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
+        ad9361.init(parameters).unwrap();
+
+        assert_eq!(
+            ad9361.load_gain_table(&mut rejected, 0),
+            Err(Ad9361Error::InvalidArgument)
+        );
+        ad9361
+            .load_gain_table(&mut gt, 1)
+            .expect("Failed to load full gain table");
+    }
+
+    /// Retune the RX LO across a recommended band edge with a gain table set
+    #[test]
+    #[serial]
+    fn gain_table_lo_band_edge() {
+        let (parameters, spi, delay, resetb, sync, heap) = test_setup();
+        let mut gt =
+            GainTable::new_from_recommended(GainTableKind::Full, 2_000_000_000);
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
+        ad9361.init(parameters).unwrap();
+        ad9361.set_rx_lo_freq(3_900_000_000).unwrap();
+        ad9361.set_gain_table(&mut gt).unwrap();
+
+        // The recommended table covers every LO frequency, so the full table
+        // (maximum index 76) stays loaded across the 4 GHz edge
+        ad9361.set_rx_lo_freq(4_100_000_000).unwrap();
+        // Within the resolution of the RF synthesizer
+        let lo = ad9361.get_rx_lo_freq().unwrap();
+        assert!(lo.abs_diff(4_100_000_000) < 10, "LO at {} Hz", lo);
+        assert_eq!(ad9361.spi_read(0x0FD), Ok(76));
+    }
+
     /// Set a Split Gain Table
     #[test]
     #[serial]
     fn set_split_gain_table() {
-        let (parameters, spi, delay, resetb, heap) = test_setup();
-        let mut ad9361 = Ad9361::new(spi, delay, Some(resetb), heap);
-        ad9361.init(parameters).unwrap();
+        let (mut parameters, spi, delay, resetb, sync, heap) = test_setup();
+        parameters.set_split_gain_table_mode_enable(1);
         let mut gt = GainTable::new_from_recommended(
             GainTableKind::Split,
             2_000_000_000,
         );
+        let mut full =
+            GainTable::new_from_recommended(GainTableKind::Full, 2_000_000_000);
+        let mut ad9361 =
+            Ad9361::new(spi, delay, Some(resetb), Some(sync), heap);
+        ad9361.init(parameters).unwrap();
+        assert_eq!(
+            ad9361.set_gain_table(&mut full),
+            Err(Ad9361Error::InvalidArgument)
+        );
 
         info!("");
         info!("Set Split Gain Table");
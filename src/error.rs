@@ -0,0 +1,180 @@
+//! Error type for AD9361
+
+use crate::interop::errno;
+
+/// Error returned by the AD9361 driver
+///
+/// The no-OS driver returns negative errno values, and the common values are
+/// mapped to named variants. The raw value is available from
+/// [`code`](Self::code)
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Ad9361Error {
+    /// Invalid argument (-EINVAL)
+    InvalidArgument,
+    /// Operation timed out (-ETIMEDOUT), for example waiting for a
+    /// calibration to complete or a PLL to lock
+    TimedOut,
+    /// Device or resource busy (-EBUSY)
+    Busy,
+    /// I/O error (-EIO)
+    Io,
+    /// Out of memory (-ENOMEM), the heap passed to `Ad9361::new` is too small
+    OutOfMemory,
+    /// No such device (-ENODEV), the device is missing or does not respond
+    /// with the AD936x product ID
+    NoDevice,
+    /// Any other error, holding the raw value
+    Unknown(i32),
+}
+impl Ad9361Error {
+    /// Raw error value, as returned by the no-OS driver
+    pub fn code(&self) -> i32 {
+        match self {
+            Self::InvalidArgument => -errno::EINVAL,
+            Self::TimedOut => -errno::ETIMEDOUT,
+            Self::Busy => -errno::EBUSY,
+            Self::Io => -errno::EIO,
+            Self::OutOfMemory => -errno::ENOMEM,
+            Self::NoDevice => -errno::ENODEV,
+            Self::Unknown(code) => *code,
+        }
+    }
+}
+impl From<i32> for Ad9361Error {
+    fn from(code: i32) -> Self {
+        match -code {
+            errno::EINVAL => Self::InvalidArgument,
+            errno::ETIMEDOUT => Self::TimedOut,
+            errno::EBUSY => Self::Busy,
+            errno::EIO => Self::Io,
+            errno::ENOMEM => Self::OutOfMemory,
+            errno::ENODEV => Self::NoDevice,
+            _ => Self::Unknown(code),
+        }
+    }
+}
+impl core::fmt::Display for Ad9361Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            Self::InvalidArgument => write!(f, "invalid argument"),
+            Self::TimedOut => write!(f, "timed out"),
+            Self::Busy => write!(f, "device busy"),
+            Self::Io => write!(f, "I/O error"),
+            Self::OutOfMemory => write!(f, "out of memory"),
+            Self::NoDevice => write!(f, "no such device"),
+            Self::Unknown(code) => write!(f, "error {}", code),
+        }
+    }
+}
+
+/// Error returned when validating the clock configuration
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ClockError {
+    /// The reference clock is outside the range 10 MHz to 80 MHz
+    ReferenceOutOfRange,
+    /// The BBPLL frequency (the first RX or TX path clock) is outside the
+    /// range 715 MHz to 1430 MHz
+    BbpllOutOfRange,
+}
+impl core::fmt::Display for ClockError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            Self::ReferenceOutOfRange => write!(f, "reference out of range"),
+            Self::BbpllOutOfRange => write!(f, "BBPLL out of range"),
+        }
+    }
+}
+
+/// Error returned when building initialisation parameters
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum InitParamError {
+    /// FDD mode is enabled together with the half duplex data interface
+    FddWithHalfDuplex,
+    /// FDD independent mode is enabled without FDD mode
+    IndependentWithoutFdd,
+    /// The RX or TX LO frequency is outside the range of the device
+    LoOutOfRange,
+    /// The sampling rate cannot be generated with a valid clock chain
+    SamplingRateOutOfRange,
+}
+impl core::fmt::Display for InitParamError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            Self::FddWithHalfDuplex => write!(f, "FDD with half duplex"),
+            Self::IndependentWithoutFdd => {
+                write!(f, "FDD independent mode without FDD")
+            }
+            Self::LoOutOfRange => write!(f, "LO out of range"),
+            Self::SamplingRateOutOfRange => {
+                write!(f, "sampling rate out of range")
+            }
+        }
+    }
+}
+
+/// Error returned when validating a FIR filter configuration
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FirError {
+    /// The number of coefficients is not a multiple of 16 in the range 16 to
+    /// 128, or is greater than 64 with a TX interpolation of 1
+    InvalidLength,
+    /// The gain is not one of the values supported by the filter, -6 dB or 0
+    /// dB for TX and -12 dB, -6 dB, 0 dB or 6 dB for RX
+    InvalidGain,
+    /// The interpolation or decimation is not 1, 2 or 4
+    InvalidRate,
+}
+impl core::fmt::Display for FirError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            Self::InvalidLength => write!(f, "invalid number of coefficients"),
+            Self::InvalidGain => write!(f, "invalid gain"),
+            Self::InvalidRate => {
+                write!(f, "invalid interpolation or decimation")
+            }
+        }
+    }
+}
+
+/// Error returned when validating a gain table entry
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GainEntryError {
+    /// The index is outside the range of the gain table kind, 1 to 77 for a
+    /// full table or 1 to 41 for a split table
+    IndexOutOfRange,
+    /// The absolute gain is outside the range -10 dB to 73 dB
+    AbsGainOutOfRange,
+    /// A reserved bit is set in register 0x132 or 0x133
+    ReservedBits,
+    /// The LPF gain is greater than 24 dB
+    LpfGainOutOfRange,
+    /// A split gain table entry sets the digital gain, which is controlled
+    /// separately in split table mode
+    DigitalGainInSplitTable,
+}
+impl core::fmt::Display for GainEntryError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            Self::IndexOutOfRange => write!(f, "index out of range"),
+            Self::AbsGainOutOfRange => write!(f, "absolute gain out of range"),
+            Self::ReservedBits => write!(f, "reserved bits set"),
+            Self::LpfGainOutOfRange => write!(f, "LPF gain out of range"),
+            Self::DigitalGainInSplitTable => {
+                write!(f, "digital gain in split table")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_codes() {
+        assert_eq!(Ad9361Error::from(-22), Ad9361Error::InvalidArgument);
+        assert_eq!(Ad9361Error::from(-1), Ad9361Error::Unknown(-1));
+        assert_eq!(Ad9361Error::InvalidArgument.code(), -22);
+        assert_eq!(Ad9361Error::from(-1).code(), -1);
+    }
+}
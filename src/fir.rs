@@ -1,6 +1,7 @@
 //! FIR filter configuration
 
 use crate::bindings;
+use crate::error::FirError;
 use paste::paste;
 
 /// Parameters used to configure the Tx FIR filter
@@ -123,6 +124,14 @@ macro_rules! get_set_inner_coefficents {
                     self.0.$property[..len].copy_from_slice(coefficients);
                     self
                 }
+                /// Builder method to set all 128
+                #[doc = $doc]
+                #[must_use]
+                pub fn [< $property _array >](mut self, coefficients: &[i16; 128]) -> Self {
+                    self.0.[< $property _size >] = 128;
+                    self.0.$property = *coefficients;
+                    self
+                }
                 /// Get
                 #[doc = $doc]
                 pub fn [< get_ $property>](&self) -> &[i16] {
@@ -148,6 +157,73 @@ get_set_inner_value!(
 );
 get_set_inner_coefficents!(Ad9361RxFir, rx_coef, "FIR Coefficients");
 
+/// Check the number of coefficients, and warn if the DC gain of the filter
+/// including `gain_db` is greater than unity, which may overflow
+fn validate_coefficients(
+    coefficients: &[i16],
+    max_len: usize,
+    gain_db: i32,
+    rate: u32,
+) -> Result<(), FirError> {
+    let len = coefficients.len();
+    if len == 0 || len > max_len || len & 0xF != 0 {
+        return Err(FirError::InvalidLength);
+    }
+    // coefficients are scaled so that 32768 is unity gain; for interpolation
+    // each phase has a share of the sum
+    let sum: i64 = coefficients.iter().map(|&c| i64::from(c)).sum();
+    let scaled = sum.abs() << ((gain_db + 12) / 6);
+    if scaled > 4 * 32768 * i64::from(rate) {
+        warn!(
+            "AD936x: FIR DC gain above 0 dB may overflow (sum of coefficients {}, gain {} dB)",
+            sum, gain_db
+        );
+    }
+    Ok(())
+}
+
+impl Ad9361TxFir {
+    /// Check that the configuration is supported by the TX FIR filter
+    ///
+    /// The filter has a multiple of 16 coefficients up to 128, or up to 64
+    /// with an interpolation of 1. The gain is -6 dB or 0 dB, and the
+    /// interpolation 1, 2 or 4. A warning is logged if the DC gain of the
+    /// filter is above 0 dB
+    pub fn validate(&self) -> Result<(), FirError> {
+        let interpolation = self.0.tx_int;
+        if !matches!(interpolation, 1 | 2 | 4) {
+            return Err(FirError::InvalidRate);
+        }
+        if !matches!(self.0.tx_gain, -6 | 0) {
+            return Err(FirError::InvalidGain);
+        }
+        let max_len = if interpolation == 1 { 64 } else { 128 };
+        validate_coefficients(
+            self.get_tx_coef(),
+            max_len,
+            self.0.tx_gain,
+            interpolation,
+        )
+    }
+}
+
+impl Ad9361RxFir {
+    /// Check that the configuration is supported by the RX FIR filter
+    ///
+    /// The filter has a multiple of 16 coefficients up to 128. The gain is -12
+    /// dB, -6 dB, 0 dB or 6 dB, and the decimation 1, 2 or 4. A warning is
+    /// logged if the DC gain of the filter is above 0 dB
+    pub fn validate(&self) -> Result<(), FirError> {
+        if !matches!(self.0.rx_dec, 1 | 2 | 4) {
+            return Err(FirError::InvalidRate);
+        }
+        if !matches!(self.0.rx_gain, -12 | -6 | 0 | 6) {
+            return Err(FirError::InvalidGain);
+        }
+        validate_coefficients(self.get_rx_coef(), 128, self.0.rx_gain, 1)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -177,6 +253,33 @@ mod tests {
         assert_eq!(txfir.get_tx_coef(), &[0x55; 10]);
     }
 
+    #[test]
+    fn set_coefficient_arrays() {
+        let txfir = Ad9361TxFir::default().tx_coef_array(&[0x55; 128]);
+        assert_eq!(txfir.get_tx_coef(), &[0x55; 128]);
+
+        let rxfir = Ad9361RxFir::default().rx_coef_array(&[-3; 128]);
+        assert_eq!(rxfir.get_rx_coef(), &[-3; 128]);
+    }
+
+    #[test]
+    fn validate_fir() {
+        assert_eq!(Ad9361TxFir::default().validate(), Ok(()));
+        assert_eq!(Ad9361RxFir::default().validate(), Ok(()));
+
+        let txfir = Ad9361TxFir::default().tx_coef(&[0x55; 10]);
+        assert_eq!(txfir.validate(), Err(FirError::InvalidLength));
+        let txfir = Ad9361TxFir::default().tx_coef_array(&[0x55; 128]);
+        assert_eq!(txfir.validate(), Err(FirError::InvalidLength));
+        assert_eq!(txfir.tx_int(2).validate(), Ok(()));
+        let txfir = Ad9361TxFir::default().tx_gain(6);
+        assert_eq!(txfir.validate(), Err(FirError::InvalidGain));
+
+        let rxfir = Ad9361RxFir::default().rx_gain(6).rx_dec(3);
+        assert_eq!(rxfir.validate(), Err(FirError::InvalidRate));
+        assert_eq!(rxfir.rx_dec(4).validate(), Ok(()));
+    }
+
     #[test]
     #[should_panic]
     fn set_tx_coefficients_too_long() {
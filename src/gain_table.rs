@@ -1,6 +1,8 @@
 //! Gain table configuration
 
 use crate::bindings;
+use crate::error::GainEntryError;
+use core::ops::RangeInclusive;
 use getset::{CopyGetters, Setters};
 
 /// The AD9361 supports both full and split gain tables
@@ -9,6 +11,15 @@ pub enum GainTableKind {
     Full,
     Split,
 }
+impl GainTableKind {
+    /// Number of entries in a gain table of this kind
+    pub const fn size(self) -> usize {
+        match self {
+            Self::Full => 77,  // SIZE_FULL_TABLE
+            Self::Split => 41, // SIZE_SPLIT_TABLE
+        }
+    }
+}
 
 /// Gain table
 ///
@@ -16,16 +27,22 @@ pub enum GainTableKind {
 /// [example
 /// project](https://github.com/analogdevicesinc/no-OS/tree/master/projects/ad9361/src)
 /// in the [no-OS](https://github.com/analogdevicesinc/no-OS) library.
+///
+/// A gain table holds up to 6 bands, see
+/// [`new_multiband`](Self::new_multiband). The driver selects the band for the
+/// current RX LO frequency, and reloads the table when the RX LO frequency
+/// crosses into a different band.
 #[derive(Clone, Copy, Debug)]
 #[repr(C)]
 pub struct GainTable {
     // need to use 6 so that split gain table is at the right index for
-    // midfrequency lna_table and mixer_table lookups
-    info: [bindings::gain_table_info; 6],
-    // index of the active gain info table
+    // midfrequency lna_table and mixer_table lookups, followed by an unused
+    // info whose NULL table ends the driver's search
+    info: [bindings::gain_table_info; 7],
+    // index of the selected gain info table
     index: usize,
-    table: [[u8; 3]; 90],
-    abs_gain_tbl: [i8; 90],
+    table: [[[u8; 3]; 90]; 6],
+    abs_gain_tbl: [[i8; 90]; 6],
 }
 impl GainTable {
     /// Set internal self-referential pointers and return a pointer to the
@@ -35,10 +52,16 @@ impl GainTable {
     ///
     /// `self` must not be moved after calling this method
     pub(crate) unsafe fn set_ptr(&mut self) -> *mut bindings::gain_table_info {
-        // set self-referential fields in info
-        for i in 0..=self.index {
-            self.info[i].tab = &mut self.table[0];
-            self.info[i].abs_gain_tbl = &mut self.abs_gain_tbl[0];
+        // the driver stops searching at the first info without a table, so
+        // set self-referential fields in all info up to the last band, and
+        // never in the unused info that follows the 6 bands
+        let last = (0..6)
+            .rev()
+            .find(|&i| self.info[i].max_index > 0)
+            .map_or(self.index, |i| i.max(self.index));
+        for i in 0..=last {
+            self.info[i].tab = &mut self.table[i][0];
+            self.info[i].abs_gain_tbl = &mut self.abs_gain_tbl[i][0];
         }
         // return pointer to array of gain info structs
         &mut self.info[0]
@@ -56,37 +79,138 @@ pub struct GainEntry {
     abs_gain: i8,
 }
 
+impl GainEntry {
+    /// New gain entry from the contents of the gain table registers
+    pub(crate) const fn new(
+        reg131: u8,
+        reg132: u8,
+        reg133: u8,
+        abs_gain: i8,
+    ) -> Self {
+        Self {
+            reg131,
+            reg132,
+            reg133,
+            abs_gain,
+        }
+    }
+    /// Check the entry for values that are invalid in a gain table of the
+    /// given kind
+    ///
+    /// Register 0x132 holds the TIA gain (bit 5) and the LPF gain (bits 4:0,
+    /// at most 24 dB). Register 0x133 holds the DC calibration flag (bit 5)
+    /// and the digital gain (bits 4:0), which must be zero in a split table.
+    /// The absolute gain must be in the range of the recommended tables, -10
+    /// dB to 73 dB
+    pub fn validate(&self, kind: GainTableKind) -> Result<(), GainEntryError> {
+        if !(GainTable::ABS_GAIN_MIN..=GainTable::ABS_GAIN_MAX)
+            .contains(&self.abs_gain)
+        {
+            return Err(GainEntryError::AbsGainOutOfRange);
+        }
+        if self.reg132 & 0xC0 != 0 || self.reg133 & 0xC0 != 0 {
+            return Err(GainEntryError::ReservedBits);
+        }
+        if self.reg132 & 0x1F > 24 {
+            return Err(GainEntryError::LpfGainOutOfRange);
+        }
+        if kind == GainTableKind::Split && self.reg133 & 0x1F != 0 {
+            return Err(GainEntryError::DigitalGainInSplitTable);
+        }
+        Ok(())
+    }
+}
+
 /// Methods for mutating the gain table set
 impl GainTable {
+    /// Minimum absolute gain of an entry, in dB
+    pub const ABS_GAIN_MIN: i8 = -10;
+    /// Maximum absolute gain of an entry, in dB
+    pub const ABS_GAIN_MAX: i8 = 73;
+
     /// Returns the entry at index from a gain table
     ///
     /// index in the range [1, 90]
     pub fn get_entry(&self, index: usize) -> GainEntry {
         debug_assert!(index > 0);
-        debug_assert!(index <= self.info[0].max_index.into());
+        debug_assert!(index <= self.max_index());
 
         GainEntry {
-            reg131: self.table[index - 1][0],
-            reg132: self.table[index - 1][1],
-            reg133: self.table[index - 1][2],
-            abs_gain: self.abs_gain_tbl[index - 1],
+            reg131: self.table[self.index][index - 1][0],
+            reg132: self.table[self.index][index - 1][1],
+            reg133: self.table[self.index][index - 1][2],
+            abs_gain: self.abs_gain_tbl[self.index][index - 1],
         }
     }
     /// Sets the entry at index in a given gain table. If not already the case,
     /// expands the table to at least `index` entries.
     ///
-    /// index must be less than 90
+    /// index in the range [1, [`GainTableKind::size`]]. Panics if index is
+    /// outside this range
     pub fn set_entry(&mut self, index: usize, e: GainEntry) {
-        debug_assert!(index > 0);
-        debug_assert!(index <= 90);
+        let size = self.kind().size();
+        assert!(
+            index > 0 && index <= size,
+            "gain table index {} outside 1..={} for a {:?} table",
+            index,
+            size,
+            self.kind()
+        );
+        debug_assert!(
+            (Self::ABS_GAIN_MIN..=Self::ABS_GAIN_MAX).contains(&e.abs_gain),
+            "abs_gain {} out of range",
+            e.abs_gain
+        );
 
-        self.table[index - 1][0] = e.reg131;
-        self.table[index - 1][1] = e.reg132;
-        self.table[index - 1][2] = e.reg133;
-        self.abs_gain_tbl[index - 1] = e.abs_gain;
+        self.table[self.index][index - 1][0] = e.reg131;
+        self.table[self.index][index - 1][1] = e.reg132;
+        self.table[self.index][index - 1][2] = e.reg133;
+        self.abs_gain_tbl[self.index][index - 1] = e.abs_gain;
         self.info[self.index].max_index =
             core::cmp::max(index as u8, self.info[self.index].max_index);
     }
+    /// Sets the entry at index in a given gain table, after checking it with
+    /// [`GainEntry::validate`]. If not already the case, expands the table to
+    /// at least `index` entries.
+    ///
+    /// index in the range [1, [`GainTableKind::size`]]
+    pub fn try_set_entry(
+        &mut self,
+        index: usize,
+        e: GainEntry,
+    ) -> Result<(), GainEntryError> {
+        if !(1..=self.kind().size()).contains(&index) {
+            return Err(GainEntryError::IndexOutOfRange);
+        }
+        e.validate(self.kind())?;
+        self.set_entry(index, e);
+        Ok(())
+    }
+    /// Iterate over the entries of the gain table, from index 1 to
+    /// [`max_index`](Self::max_index) inclusive
+    pub fn entries(&self) -> impl Iterator<Item = (usize, GainEntry)> + '_ {
+        (1..=self.max_index()).map(move |index| (index, self.get_entry(index)))
+    }
+    /// Replace each entry of the gain table, from index 1 to
+    /// [`max_index`](Self::max_index) inclusive, with the result of `f`
+    ///
+    /// For example, to shift the absolute gain of all entries:
+    ///
+    /// ```ignore
+    /// gt.map_entries(|_, mut e| {
+    ///     e.set_abs_gain(e.abs_gain() - 3);
+    ///     e
+    /// });
+    /// ```
+    pub fn map_entries(
+        &mut self,
+        mut f: impl FnMut(usize, GainEntry) -> GainEntry,
+    ) {
+        for index in 1..=self.max_index() {
+            let e = f(index, self.get_entry(index));
+            self.set_entry(index, e);
+        }
+    }
     /// Gain table kind
     pub fn kind(&self) -> GainTableKind {
         match self.info[self.index].split_table {
@@ -98,31 +222,89 @@ impl GainTable {
     pub fn max_index(&self) -> usize {
         self.info[self.index].max_index.into()
     }
+    /// Range of RX LO frequencies (in Hz) that the selected band is valid for
+    ///
+    /// When the RX LO is tuned outside the range of all bands, load a gain
+    /// table for the new frequency
+    pub fn frequency_range(&self) -> RangeInclusive<u64> {
+        // the driver excludes the start frequency of the band
+        self.info[self.index].start + 1..=self.info[self.index].end
+    }
+    /// Select the band containing `frequency` (in Hz), which subsequent
+    /// methods of this type then operate on
+    ///
+    /// Returns false, and leaves the selection unchanged, if no band contains
+    /// `frequency`
+    pub fn select_band(&mut self, frequency: u64) -> bool {
+        // as ad9361_gt_tableindex, which excludes the start frequency
+        match (0..6).find(|&i| {
+            self.info[i].max_index > 0
+                && self.info[i].start < frequency
+                && frequency <= self.info[i].end
+        }) {
+            Some(i) => {
+                self.index = i;
+                true
+            }
+            None => false,
+        }
+    }
+    /// True if every band of this gain table is of the given kind
+    pub(crate) fn is_kind(&self, kind: GainTableKind) -> bool {
+        let split = matches!(kind, GainTableKind::Split) as u8;
+        self.info
+            .iter()
+            .filter(|info| info.max_index > 0)
+            .all(|info| info.split_table == split)
+    }
 }
 
 impl GainTable {
+    /// Frequency bands (in Hz) of the recommended gain tables, centred on 800
+    /// MHz, 2300 MHz and 5500 MHz
+    const RECOMMENDED_BANDS: [(u64, u64); 3] = [
+        (0, 1_299_999_999),
+        (1_300_000_000, 3_999_999_999),
+        (4_000_000_000, 6_000_000_000),
+    ];
+    /// Info of an unused band. The table of the last info stays NULL, which
+    /// ends the driver's search for a band
+    const INFO_UNUSED: bindings::gain_table_info = bindings::gain_table_info {
+        start: 0,
+        end: 0,
+        max_index: 0,
+        split_table: 0,
+        abs_gain_tbl: core::ptr::null_mut(),
+        tab: core::ptr::null_mut(),
+    };
+
+    /// Index of the recommended band containing `frequency`
+    const fn recommended_band(frequency: u64) -> usize {
+        if frequency < 1_300_000_000 {
+            0
+        } else if frequency < 4_000_000_000 {
+            1
+        } else {
+            2
+        }
+    }
+
     /// New gain table, with default values from the [example
     /// project](https://github.com/analogdevicesinc/no-OS/tree/master/projects/ad9361/src)
     /// in the [no-OS](https://github.com/analogdevicesinc/no-OS) library.
+    ///
+    /// The table for the band containing `frequency` is selected, from the
+    /// bands with edges at 1.3 GHz and 4 GHz. Its
+    /// [`frequency_range`](Self::frequency_range) covers every RX LO
+    /// frequency, so the driver keeps using it when the RX LO moves into
+    /// another band. For a table that follows the RX LO between bands, see
+    /// [`new_multiband`](Self::new_multiband)
     pub const fn new_from_recommended(
         kind: GainTableKind,
         frequency: u64,
     ) -> Self {
-        let index = if frequency < 1_300_000_000 {
-            0
-        } else if frequency < 4_000_000_000 {
-            1
-        } else {
-            2
-        };
-        let gt_null = bindings::gain_table_info {
-            start: 0,
-            end: 0,
-            max_index: 0,
-            split_table: 0,
-            abs_gain_tbl: core::ptr::null_mut(),
-            tab: core::ptr::null_mut(),
-        };
+        let index = Self::recommended_band(frequency);
+        let gt_null = Self::INFO_UNUSED;
         if matches!(kind, GainTableKind::Full) {
             let info = [
                 bindings::gain_table_info {
@@ -138,6 +320,7 @@ impl GainTable {
                 gt_null,
                 gt_null,
                 gt_null,
+                gt_null,
             ];
 
             #[rustfmt::skip]
@@ -267,11 +450,16 @@ impl GainTable {
                 ],
             ];
 
+            let mut tables = [[[0; 3]; 90]; 6];
+            tables[0] = table[index];
+            let mut abs_gain_tbls = [[0; 90]; 6];
+            abs_gain_tbls[0] = abs_gain_tbl[index];
+
             GainTable {
                 info,
                 index: 0, // active table at index 0
-                abs_gain_tbl: abs_gain_tbl[index],
-                table: table[index],
+                abs_gain_tbl: abs_gain_tbls,
+                table: tables,
             }
         } else {
             let info = [
@@ -288,6 +476,7 @@ impl GainTable {
                     tab: core::ptr::null_mut(),
                 },
                 gt_null,
+                gt_null,
             ];
 
             #[rustfmt::skip]
@@ -395,14 +584,77 @@ impl GainTable {
                 ],
             ];
 
+            let mut tables = [[[0; 3]; 90]; 6];
+            tables[4] = table[index];
+            let mut abs_gain_tbls = [[0; 90]; 6];
+            abs_gain_tbls[4] = abs_gain_tbl[index];
+
             GainTable {
                 info,
                 index: 4, // active info at index 4
-                abs_gain_tbl: abs_gain_tbl[index],
-                table: table[index],
+                abs_gain_tbl: abs_gain_tbls,
+                table: tables,
             }
         }
     }
+    /// New gain table with several bands, each covering a range of RX LO
+    /// frequencies (in Hz). The driver selects the band for the current RX LO
+    /// frequency, and reloads the table when the RX LO frequency moves into
+    /// a different band.
+    ///
+    /// Each band is initialised with the recommended table for the start of
+    /// its range, see [`new_from_recommended`](Self::new_from_recommended),
+    /// and can then be modified after selecting it with
+    /// [`select_band`](Self::select_band). The first band is selected
+    /// initially.
+    ///
+    /// All the bands must be of the same kind, which must match
+    /// `split_gain_table_mode_enable` in the initialisation parameters. Up to
+    /// 3 bands are supported, with at most one starting in each of the
+    /// recommended bands (edges at 1.3 GHz and 4 GHz). Panics if `bands` is
+    /// empty or breaks these rules.
+    pub fn new_multiband(
+        bands: &[(GainTableKind, RangeInclusive<u64>)],
+    ) -> Self {
+        assert!(!bands.is_empty(), "gain table must have at least one band");
+        assert!(
+            bands.iter().all(|(kind, _)| *kind == bands[0].0),
+            "gain table bands must all be of the same kind"
+        );
+
+        let mut gt = GainTable {
+            info: [Self::INFO_UNUSED; 7],
+            index: 0,
+            table: [[[0; 3]; 90]; 6],
+            abs_gain_tbl: [[0; 90]; 6],
+        };
+        for (i, (kind, range)) in bands.iter().enumerate() {
+            let recommended = Self::new_from_recommended(*kind, *range.start());
+            // full tables at 0 to 2 and split tables at 3 to 5, to match the
+            // lna_table and mixer_table lookups
+            let slot = match kind {
+                GainTableKind::Full => 0,
+                GainTableKind::Split => 3,
+            } + Self::recommended_band(*range.start());
+            assert!(
+                gt.info[slot].max_index == 0,
+                "more than one {:?} gain table band starts in {:?}",
+                kind,
+                Self::RECOMMENDED_BANDS[slot % 3]
+            );
+
+            gt.info[slot] = recommended.info[recommended.index];
+            // the driver excludes the start frequency of each band
+            gt.info[slot].start = range.start().saturating_sub(1);
+            gt.info[slot].end = *range.end();
+            gt.table[slot] = recommended.table[recommended.index];
+            gt.abs_gain_tbl[slot] = recommended.abs_gain_tbl[recommended.index];
+            if i == 0 {
+                gt.index = slot;
+            }
+        }
+        gt
+    }
 }
 
 #[cfg(test)]
@@ -415,6 +667,89 @@ mod tests {
             GainTable::new_from_recommended(GainTableKind::Full, 2_000_000_000);
     }
 
+    #[test]
+    fn gain_table_frequency_range() {
+        let gt =
+            GainTable::new_from_recommended(GainTableKind::Full, 2_000_000_000);
+        assert_eq!(gt.frequency_range(), 1..=6_000_000_000);
+        let gt = GainTable::new_from_recommended(
+            GainTableKind::Split,
+            5_000_000_000,
+        );
+        assert_eq!(gt.frequency_range(), 1..=6_000_000_000);
+    }
+
+    #[test]
+    fn multiband_gain_table() {
+        let mut gt = GainTable::new_multiband(&[
+            (GainTableKind::Full, 70_000_000..=1_499_999_999),
+            (GainTableKind::Full, 1_500_000_000..=6_000_000_000),
+        ]);
+        assert_eq!(gt.frequency_range(), 70_000_000..=1_499_999_999);
+        assert_eq!(gt.get_entry(1).abs_gain(), -1);
+
+        assert!(gt.select_band(2_400_000_000));
+        assert_eq!(gt.frequency_range(), 1_500_000_000..=6_000_000_000);
+        assert_eq!(gt.get_entry(1).abs_gain(), -3);
+        assert!(!gt.select_band(10_000_000));
+        assert_eq!(gt.max_index(), 77);
+        assert!(gt.is_kind(GainTableKind::Full));
+        assert!(!gt.is_kind(GainTableKind::Split));
+    }
+
+    /// Band index found by the driver's ad9361_gt_tableindex
+    fn driver_band(gt: &mut GainTable, frequency: u64) -> Option<usize> {
+        let info = unsafe { gt.set_ptr() };
+        (0..)
+            .map(|i| unsafe { *info.add(i) })
+            .take_while(|info| !info.tab.is_null())
+            .position(|info| info.start < frequency && frequency <= info.end)
+    }
+
+    #[test]
+    fn multiband_gain_table_band_edges() {
+        let mut gt = GainTable::new_multiband(&[
+            (GainTableKind::Split, 70_000_000..=1_499_999_999),
+            (GainTableKind::Split, 1_500_000_000..=4_999_999_999),
+            (GainTableKind::Split, 5_000_000_000..=6_000_000_000),
+        ]);
+        for (frequency, band) in [
+            (69_999_999, None),
+            (70_000_000, Some(3)),
+            (1_499_999_999, Some(3)),
+            (1_500_000_000, Some(4)),
+            (5_000_000_000, Some(5)),
+            (6_000_000_000, Some(5)),
+            // past the last band, the search ends at the unused info
+            (6_000_000_001, None),
+        ] {
+            assert_eq!(driver_band(&mut gt, frequency), band, "{}", frequency);
+            assert_eq!(gt.select_band(frequency), band.is_some());
+            if let Some(band) = band {
+                assert_eq!(gt.index, band);
+            }
+        }
+        assert_eq!(gt.frequency_range(), 5_000_000_000..=6_000_000_000);
+    }
+
+    #[test]
+    #[should_panic(expected = "same kind")]
+    fn multiband_gain_table_mixed_kinds() {
+        let _ = GainTable::new_multiband(&[
+            (GainTableKind::Full, 70_000_000..=1_299_999_999),
+            (GainTableKind::Split, 1_300_000_000..=6_000_000_000),
+        ]);
+    }
+
+    #[test]
+    #[should_panic(expected = "more than one")]
+    fn multiband_gain_table_overlap() {
+        let _ = GainTable::new_multiband(&[
+            (GainTableKind::Split, 1_300_000_000..=2_000_000_000),
+            (GainTableKind::Split, 2_000_000_001..=4_000_000_000),
+        ]);
+    }
+
     #[test]
     fn set_gain_entry() {
         let mut gt = GainTable::new_from_recommended(GainTableKind::Full, 0);
@@ -423,6 +758,67 @@ mod tests {
         gt.set_entry(1, ge);
     }
 
+    #[test]
+    fn gain_table_entries() {
+        let gt = GainTable::new_from_recommended(GainTableKind::Split, 0);
+        assert_eq!(gt.entries().count(), 41);
+        assert_eq!(gt.entries().last().unwrap().1.abs_gain(), 49);
+    }
+
+    #[test]
+    fn map_gain_entries() {
+        let mut gt = GainTable::new_from_recommended(GainTableKind::Full, 0);
+        gt.map_entries(|_, mut e| {
+            e.set_abs_gain(e.abs_gain() - 3);
+            e
+        });
+        assert_eq!(gt.get_entry(4).abs_gain(), -3);
+        assert_eq!(gt.max_index(), 77);
+    }
+
+    #[test]
+    fn recommended_gain_entries_are_valid() {
+        for kind in [GainTableKind::Full, GainTableKind::Split] {
+            for frequency in [800_000_000, 2_300_000_000, 5_500_000_000] {
+                let gt = GainTable::new_from_recommended(kind, frequency);
+                for (_, e) in gt.entries() {
+                    assert_eq!(e.validate(kind), Ok(()));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn try_set_gain_entry() {
+        let mut gt = GainTable::new_from_recommended(GainTableKind::Split, 0);
+        let mut ge = gt.get_entry(10);
+        ge.set_reg133(0x21);
+        assert_eq!(
+            gt.try_set_entry(10, ge),
+            Err(GainEntryError::DigitalGainInSplitTable)
+        );
+        ge.set_reg133(0x20);
+        ge.set_abs_gain(80);
+        assert_eq!(
+            gt.try_set_entry(10, ge),
+            Err(GainEntryError::AbsGainOutOfRange)
+        );
+        ge.set_abs_gain(20);
+        assert_eq!(
+            gt.try_set_entry(42, ge),
+            Err(GainEntryError::IndexOutOfRange)
+        );
+        assert_eq!(gt.try_set_entry(10, ge), Ok(()));
+    }
+
+    #[test]
+    #[should_panic(expected = "outside 1..=41")]
+    fn set_gain_entry_beyond_split_table() {
+        let mut gt = GainTable::new_from_recommended(GainTableKind::Split, 0);
+        let ge = gt.get_entry(1);
+        gt.set_entry(42, ge);
+    }
+
     #[test]
     #[should_panic]
     fn get_gain_entry_out_of_bounds() {
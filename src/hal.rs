@@ -0,0 +1,266 @@
+//! Hardware abstraction traits
+//!
+//! The driver is generic over these traits, which are implemented for every
+//! type implementing the corresponding embedded-hal traits. By default these
+//! are the embedded-hal 0.2 traits. With the `eh1` feature, the embedded-hal
+//! 1.0 traits are used instead:
+//!
+//! | Trait         | embedded-hal 0.2                   | embedded-hal 1.0 (`eh1`) |
+//! |---------------|------------------------------------|--------------------------|
+//! | [`Spi`]       | `blocking::spi::Transfer<u8>`      | `spi::SpiDevice<u8>`     |
+//! | [`Delay`]     | `blocking::delay::DelayMs<u32>` + `blocking::delay::DelayUs<u32>` | `delay::DelayNs` |
+//! | [`OutputPin`] | `digital::v2::OutputPin`           | `digital::OutputPin`     |
+//! | [`InputPin`]  | `digital::v2::InputPin`            | `digital::InputPin`      |
+//!
+//! With embedded-hal 1.0, each transfer from the C driver is a single
+//! `SpiDevice` transaction, so the chip select is handled by the `SpiDevice`
+//! implementation.
+//!
+//! A SPI peripheral configured for 16-bit words implements the `u16` version
+//! of the SPI trait instead. Wrap it in [`Spi16`] to use it with the driver.
+//!
+//! With embedded-hal 0.2 the chip select is not part of the SPI trait. If it
+//! is not driven by the SPI peripheral, for example on a shared bus, pair the
+//! SPI peripheral with an output pin in [`SpiCs`].
+
+/// Error from the underlying embedded-hal implementation
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct HalError;
+
+/// SPI peripheral
+pub trait Spi {
+    /// Full-duplex transfer, replacing `data` with the bytes read
+    fn transfer_in_place(&mut self, data: &mut [u8]) -> Result<(), HalError>;
+}
+/// Adapter for a SPI peripheral that transfers 16-bit words
+///
+/// Each transfer from the C driver is packed into 16-bit words, most
+/// significant byte first. Transfers with an odd number of bytes are padded
+/// with a trailing zero byte, whose clocks are ignored by the AD9361
+pub struct Spi16<SPI>(pub SPI);
+
+/// Largest transfer made by the C driver: two command bytes and up to eight
+/// data bytes
+const MAX_TRANSFER_WORDS16: usize = 5;
+
+/// Transfer `data` as 16-bit words with `transfer`
+fn transfer_words16(
+    data: &mut [u8],
+    transfer: impl FnOnce(&mut [u16]) -> Result<(), HalError>,
+) -> Result<(), HalError> {
+    let mut words = [0u16; MAX_TRANSFER_WORDS16];
+    if data.len() > 2 * MAX_TRANSFER_WORDS16 {
+        return Err(HalError);
+    }
+    let n = crate::transaction::Ad9361Transaction(data).to_words16(&mut words);
+    transfer(&mut words[..n])?;
+    crate::transaction::Ad9361Transaction::from_words16(&words[..n], data);
+    Ok(())
+}
+
+/// Adapter that drives a chip select pin around each transfer
+///
+/// `cs` is driven low before each transfer from the C driver, and high after
+/// it, even if the transfer fails. The inner SPI peripheral may itself be an
+/// adapter, such as [`Spi16`]
+pub struct SpiCs<SPI, CS> {
+    /// SPI peripheral
+    pub spi: SPI,
+    /// Active-low chip select
+    pub cs: CS,
+}
+impl<SPI, CS> SpiCs<SPI, CS>
+where
+    SPI: Spi,
+    CS: OutputPin,
+{
+    /// Pair a SPI peripheral with a chip select pin, and deselect the device
+    pub fn new(spi: SPI, mut cs: CS) -> Result<Self, HalError> {
+        cs.set_state(true)?;
+        Ok(Self { spi, cs })
+    }
+}
+impl<SPI, CS> Spi for SpiCs<SPI, CS>
+where
+    SPI: Spi,
+    CS: OutputPin,
+{
+    fn transfer_in_place(&mut self, data: &mut [u8]) -> Result<(), HalError> {
+        self.cs.set_state(false)?;
+        let result = self.spi.transfer_in_place(data);
+        self.cs.set_state(true)?;
+        result
+    }
+}
+
+/// Delay provider
+pub trait Delay {
+    /// Delay for `ms` milliseconds
+    fn delay_ms(&mut self, ms: u32);
+    /// Delay for `us` microseconds
+    fn delay_us(&mut self, us: u32);
+}
+/// Digital output pin
+pub trait OutputPin {
+    /// Drive the pin high if `high`, otherwise low
+    fn set_state(&mut self, high: bool) -> Result<(), HalError>;
+}
+/// Placeholder for an output pin that is not connected
+///
+/// `NoPin` has no values, so it can only be used as the type of a pin passed
+/// as `None`, see `Ad9361::new_no_reset`
+#[derive(Debug)]
+pub enum NoPin {}
+impl OutputPin for NoPin {
+    fn set_state(&mut self, _high: bool) -> Result<(), HalError> {
+        match *self {}
+    }
+}
+/// Digital input pin
+pub trait InputPin {
+    /// Is the input pin high?
+    fn is_high(&mut self) -> Result<bool, HalError>;
+}
+
+#[cfg(not(feature = "eh1"))]
+mod eh0 {
+    use super::HalError;
+    use embedded_hal::{blocking, digital};
+
+    impl<T: blocking::spi::Transfer<u8>> super::Spi for T {
+        fn transfer_in_place(
+            &mut self,
+            data: &mut [u8],
+        ) -> Result<(), HalError> {
+            self.transfer(data).map(|_| ()).map_err(|_| HalError)
+        }
+    }
+    impl<T: blocking::spi::Transfer<u16>> super::Spi for super::Spi16<T> {
+        fn transfer_in_place(
+            &mut self,
+            data: &mut [u8],
+        ) -> Result<(), HalError> {
+            super::transfer_words16(data, |words| {
+                self.0.transfer(words).map(|_| ()).map_err(|_| HalError)
+            })
+        }
+    }
+    impl<T> super::Delay for T
+    where
+        T: blocking::delay::DelayMs<u32> + blocking::delay::DelayUs<u32>,
+    {
+        fn delay_ms(&mut self, ms: u32) {
+            blocking::delay::DelayMs::delay_ms(self, ms);
+        }
+        fn delay_us(&mut self, us: u32) {
+            blocking::delay::DelayUs::delay_us(self, us);
+        }
+    }
+    impl<T: digital::v2::OutputPin> super::OutputPin for T {
+        fn set_state(&mut self, high: bool) -> Result<(), HalError> {
+            let result = if high {
+                self.set_high()
+            } else {
+                self.set_low()
+            };
+            result.map_err(|_| HalError)
+        }
+    }
+    impl<T: digital::v2::InputPin> super::InputPin for T {
+        fn is_high(&mut self) -> Result<bool, HalError> {
+            digital::v2::InputPin::is_high(self).map_err(|_| HalError)
+        }
+    }
+}
+
+#[cfg(feature = "eh1")]
+mod eh1 {
+    use super::HalError;
+    use embedded_hal_1::{delay, digital, spi};
+
+    impl<T: spi::SpiDevice<u8>> super::Spi for T {
+        fn transfer_in_place(
+            &mut self,
+            data: &mut [u8],
+        ) -> Result<(), HalError> {
+            spi::SpiDevice::transfer_in_place(self, data).map_err(|_| HalError)
+        }
+    }
+    impl<T: spi::SpiDevice<u16>> super::Spi for super::Spi16<T> {
+        fn transfer_in_place(
+            &mut self,
+            data: &mut [u8],
+        ) -> Result<(), HalError> {
+            super::transfer_words16(data, |words| {
+                spi::SpiDevice::transfer_in_place(&mut self.0, words)
+                    .map_err(|_| HalError)
+            })
+        }
+    }
+    impl<T: delay::DelayNs> super::Delay for T {
+        fn delay_ms(&mut self, ms: u32) {
+            delay::DelayNs::delay_ms(self, ms);
+        }
+        fn delay_us(&mut self, us: u32) {
+            delay::DelayNs::delay_us(self, us);
+        }
+    }
+    impl<T: digital::OutputPin> super::OutputPin for T {
+        fn set_state(&mut self, high: bool) -> Result<(), HalError> {
+            let result = if high {
+                self.set_high()
+            } else {
+                self.set_low()
+            };
+            result.map_err(|_| HalError)
+        }
+    }
+    impl<T: digital::InputPin> super::InputPin for T {
+        fn is_high(&mut self) -> Result<bool, HalError> {
+            digital::InputPin::is_high(self).map_err(|_| HalError)
+        }
+    }
+}
+
+#[cfg(all(test, feature = "eh1"))]
+mod tests {
+    use super::*;
+    use embedded_hal_1::spi;
+
+    /// SPI device transferring 16-bit words, that records each transaction
+    /// and answers every word with its complement
+    #[derive(Default)]
+    struct Device16 {
+        transactions: Vec<Vec<u16>>,
+    }
+    impl spi::ErrorType for Device16 {
+        type Error = core::convert::Infallible;
+    }
+    impl spi::SpiDevice<u16> for Device16 {
+        fn transaction(
+            &mut self,
+            operations: &mut [spi::Operation<'_, u16>],
+        ) -> Result<(), Self::Error> {
+            let mut transaction = vec![];
+            for operation in operations {
+                if let spi::Operation::TransferInPlace(words) = operation {
+                    transaction.extend_from_slice(words);
+                    words.iter_mut().for_each(|w| *w = !*w);
+                }
+            }
+            self.transactions.push(transaction);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn spi16_single_transaction() {
+        let mut spi = Spi16(Device16::default());
+        // Single byte read of register 0x123
+        let mut data = [0x01, 0x23, 0x00];
+        spi.transfer_in_place(&mut data).unwrap();
+
+        assert_eq!(spi.0.transactions, [vec![0x0123, 0x0000]]);
+        assert_eq!(data, [0xFE, 0xDC, 0xFF]);
+    }
+}
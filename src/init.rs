@@ -1,6 +1,9 @@
 //! Initialisation Parameters
 
 use crate::bindings;
+use crate::error::{ClockError, InitParamError};
+use crate::synth;
+use crate::types::ChannelMode;
 
 /// Parameters used to configure the AD9361 RF PHY
 ///
@@ -274,6 +277,227 @@ gettersetters! {
     ];
 }
 
+/// Configuration helpers
+impl Ad9361InitParam {
+    /// Configure Time Division Duplex (TDD) operation
+    ///
+    /// This clears `frequency_division_duplex_mode_enable` and
+    /// `frequency_division_duplex_independent_mode_enable`, so that the ENSM
+    /// alternates between the Rx and Tx states (FDD_MODE cleared in register
+    /// 0x013).
+    ///
+    /// When `dual_synth` is set, both the Rx and Tx synthesizers are kept
+    /// running and the Tx/Rx transitions do not require a retune
+    /// (DUAL_SYNTH_MODE set in register 0x015). Otherwise a single synthesizer
+    /// is retuned on each transition.
+    ///
+    /// When `pin_control` is set, the Tx/Rx selection is made by the TXNRX
+    /// pin rather than by SPI writes (ENABLE_ENSM_PIN_CTRL set in register
+    /// 0x014).
+    pub fn configure_tdd(
+        &mut self,
+        dual_synth: bool,
+        pin_control: bool,
+    ) -> &mut Self {
+        self.set_frequency_division_duplex_mode_enable(0)
+            .set_frequency_division_duplex_independent_mode_enable(0)
+            .set_tdd_use_dual_synth_mode_enable(dual_synth.into())
+            .set_ensm_enable_txnrx_control_enable(pin_control.into())
+    }
+
+    /// Set the reference clock rate, checking that it is valid
+    ///
+    /// The reference clock must be in the range 10 MHz to 80 MHz. When
+    /// a crystal is used with the DCXO, the datasheet range is 19 MHz to 50
+    /// MHz. The BBPLL frequency, which is the first entry of
+    /// `rx_path_clock_frequencies` and `tx_path_clock_frequencies`, must be in
+    /// the range 715 MHz to 1430 MHz.
+    ///
+    /// The clock configuration is not modified if `Err` is returned
+    pub fn set_reference_clk_rate_checked(
+        &mut self,
+        hz: u32,
+    ) -> Result<&mut Self, ClockError> {
+        const REFERENCE_HZ: core::ops::RangeInclusive<u32> =
+            10_000_000..=80_000_000;
+        const BBPLL_HZ: core::ops::RangeInclusive<u32> =
+            715_000_000..=1_430_000_000;
+
+        if !REFERENCE_HZ.contains(&hz) {
+            return Err(ClockError::ReferenceOutOfRange);
+        }
+        if !BBPLL_HZ.contains(&self.0.rx_path_clock_frequencies[0])
+            || !BBPLL_HZ.contains(&self.0.tx_path_clock_frequencies[0])
+        {
+            return Err(ClockError::BbpllOutOfRange);
+        }
+
+        Ok(self.set_reference_clk_rate(hz))
+    }
+}
+
+/// Builder for [`Ad9361InitParam`]
+///
+/// The builder starts from the [Default](#impl-Default) parameters, or from
+/// existing parameters with [`From`]. The interdependent fields are checked
+/// by [`build`](Self::build), so that invalid combinations are rejected before
+/// they reach the C driver.
+///
+/// ```ignore
+/// let parameters = Ad9361InitParam::builder()
+///     .tdd()
+///     .rx_lo(2_400_000_000)
+///     .tx_lo(2_400_000_000)
+///     .sampling_rate(15_360_000)
+///     .channels(ChannelMode::OneByOne)
+///     .build()?;
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Ad9361InitParamBuilder(Ad9361InitParam);
+
+impl Ad9361InitParam {
+    /// Start building initialisation parameters from the defaults
+    pub fn builder() -> Ad9361InitParamBuilder {
+        Ad9361InitParamBuilder(Default::default())
+    }
+}
+impl From<Ad9361InitParam> for Ad9361InitParamBuilder {
+    fn from(params: Ad9361InitParam) -> Self {
+        Self(params)
+    }
+}
+
+impl Ad9361InitParamBuilder {
+    /// Range of the BBPLL
+    const BBPLL_HZ: core::ops::RangeInclusive<u32> =
+        715_000_000..=1_430_000_000;
+    /// Maximum ADC clock frequency
+    const ADC_MAX_HZ: u32 = 640_000_000;
+    /// Maximum sampling rate
+    const SAMPLING_RATE_MAX_HZ: u32 = 61_440_000;
+
+    /// Frequency Division Duplex (FDD) operation
+    pub fn fdd(mut self) -> Self {
+        self.0.set_frequency_division_duplex_mode_enable(1);
+        self
+    }
+    /// Time Division Duplex (TDD) operation, with a single synthesizer and
+    /// SPI control of the Tx/Rx selection. See
+    /// [`Ad9361InitParam::configure_tdd`] for the other TDD options
+    pub fn tdd(mut self) -> Self {
+        self.0.configure_tdd(false, false);
+        self
+    }
+    /// RX LO frequency in Hz
+    pub fn rx_lo(mut self, hz: u64) -> Self {
+        self.0.set_rx_synthesizer_frequency_hz(hz);
+        self
+    }
+    /// TX LO frequency in Hz
+    pub fn tx_lo(mut self, hz: u64) -> Self {
+        self.0.set_tx_synthesizer_frequency_hz(hz);
+        self
+    }
+    /// RX and TX sampling rate in Hz
+    ///
+    /// The RX and TX path clocks are derived with the half-band filters
+    /// decimating / interpolating by 8 and the FIR filters bypassed, as in the
+    /// default parameters. The BBPLL runs at the lowest power of two multiple
+    /// of the ADC clock that is within its range. The rate must be between
+    /// about 1.4 MHz and 61.44 MHz, which is checked by [`build`](Self::build)
+    pub fn sampling_rate(mut self, hz: u32) -> Self {
+        let adc = hz.saturating_mul(8);
+        let mut bbpll = adc.saturating_mul(2);
+        for _ in 1..6 {
+            if bbpll >= *Self::BBPLL_HZ.start() {
+                break;
+            }
+            bbpll = bbpll.saturating_mul(2);
+        }
+
+        self.0
+            .set_rx_path_clock_frequencies([
+                bbpll,
+                adc,
+                adc / 2,
+                adc / 4,
+                hz,
+                hz,
+            ])
+            .set_tx_path_clock_frequencies([
+                bbpll,
+                adc / 2,
+                adc / 2,
+                adc / 4,
+                hz,
+                hz,
+            ]);
+        self
+    }
+    /// Number of RX and TX channels
+    ///
+    /// The AD9364 has a single RX and TX channel, and the driver always uses
+    /// [`ChannelMode::OneByOne`] for it
+    pub fn channels(mut self, mode: ChannelMode) -> Self {
+        self.0.set_two_rx_two_tx_mode_enable(
+            (mode == ChannelMode::TwoByTwo).into(),
+        );
+        self
+    }
+    /// Use the single-ended CMOS data interface instead of LVDS
+    ///
+    /// With `single_port` clear, P0 carries TX data (P0_D, or TX_D) and P1
+    /// carries RX data (P1_D, or RX_D) (dual port full duplex). With `single_port` set, RX and TX data share
+    /// P0, which requires TDD operation (single port half duplex); this is
+    /// checked by [`build`](Self::build). Data is transferred on both clock
+    /// edges when `ddr` is set, otherwise on the rising edge only.
+    ///
+    /// Port swapping and the full port options are cleared
+    pub fn cmos_interface(mut self, single_port: bool, ddr: bool) -> Self {
+        self.0
+            .set_lvds_mode_enable(0)
+            .set_single_port_mode_enable(single_port.into())
+            .set_half_duplex_mode_enable(single_port.into())
+            .set_single_data_rate_enable((!ddr).into())
+            .set_swap_ports_enable(0)
+            .set_full_port_enable(0)
+            .set_full_duplex_swap_bits_enable(0);
+        self
+    }
+
+    /// Check the parameters, and return them if valid
+    pub fn build(self) -> Result<Ad9361InitParam, InitParamError> {
+        let params = &self.0 .0;
+        let fdd = params.frequency_division_duplex_mode_enable != 0;
+
+        if fdd && params.half_duplex_mode_enable != 0 {
+            return Err(InitParamError::FddWithHalfDuplex);
+        }
+        if !fdd && params.frequency_division_duplex_independent_mode_enable != 0
+        {
+            return Err(InitParamError::IndependentWithoutFdd);
+        }
+        if !synth::LO_FREQ_RANGE_HZ
+            .contains(&params.rx_synthesizer_frequency_hz)
+            || !synth::LO_FREQ_RANGE_HZ
+                .contains(&params.tx_synthesizer_frequency_hz)
+        {
+            return Err(InitParamError::LoOutOfRange);
+        }
+
+        let rx = params.rx_path_clock_frequencies;
+        if rx[5] == 0
+            || rx[5] > Self::SAMPLING_RATE_MAX_HZ
+            || rx[1] > Self::ADC_MAX_HZ
+            || !Self::BBPLL_HZ.contains(&rx[0])
+        {
+            return Err(InitParamError::SamplingRateOutOfRange);
+        }
+
+        Ok(self.0)
+    }
+}
+
 impl Default for Ad9361InitParam {
     fn default() -> Self {
         let rx_path_clock_frequencies = [
@@ -438,3 +662,66 @@ impl Default for Ad9361InitParam {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builder_sampling_rate() {
+        let default = Ad9361InitParam::default();
+        let params = Ad9361InitParam::builder()
+            .sampling_rate(30_720_000)
+            .build()
+            .unwrap();
+        assert_eq!(
+            params.rx_path_clock_frequencies(),
+            default.rx_path_clock_frequencies()
+        );
+        assert_eq!(
+            params.tx_path_clock_frequencies(),
+            default.tx_path_clock_frequencies()
+        );
+
+        let params = Ad9361InitParam::builder().sampling_rate(2_000_000);
+        assert!(params.build().is_ok());
+        let params = Ad9361InitParam::builder().sampling_rate(1_000_000);
+        assert_eq!(
+            params.build().unwrap_err(),
+            InitParamError::SamplingRateOutOfRange
+        );
+    }
+
+    #[test]
+    fn builder_duplex() {
+        let mut params = Ad9361InitParam::default();
+        params.set_half_duplex_mode_enable(1);
+
+        let builder = Ad9361InitParamBuilder::from(params);
+        assert_eq!(
+            builder.fdd().build().unwrap_err(),
+            InitParamError::FddWithHalfDuplex
+        );
+        assert!(builder.tdd().build().is_ok());
+    }
+
+    #[test]
+    fn builder_cmos_interface() {
+        let params = Ad9361InitParam::builder()
+            .cmos_interface(false, true)
+            .build()
+            .unwrap();
+        assert_eq!(params.lvds_mode_enable(), 0);
+        assert_eq!(params.single_data_rate_enable(), 0);
+        assert_eq!(params.half_duplex_mode_enable(), 0);
+
+        let builder = Ad9361InitParam::builder().cmos_interface(true, false);
+        assert_eq!(
+            builder.fdd().build().unwrap_err(),
+            InitParamError::FddWithHalfDuplex
+        );
+        let params = builder.tdd().build().unwrap();
+        assert_eq!(params.single_port_mode_enable(), 1);
+        assert_eq!(params.single_data_rate_enable(), 1);
+    }
+}
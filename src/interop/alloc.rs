@@ -5,12 +5,15 @@
 //! Allocations shorter than 8 bytes are made from a scratchpad. Only one
 //! allocation is made at a time.
 //!
-//! Larger allocations are made from a heap. Only the most recent allocation is
+//! Larger allocations are made from a heap, in units of 8 bytes so that each
+//! allocation is aligned as by `malloc`. Only the most recent allocation is
 //! mutable, while previous allocations are immutable. The only exception is
 //! that freeing the allocation at the very start of the heap results in all
 //! allocations being freed.
 //!
-//! The allocator is *not* re-entrant.
+//! Each driver instance owns its own [`Heap`], which is made active with
+//! [`set_heap`] before calling into the C driver. The allocator is *not*
+//! re-entrant.
 //!
 //! The behaviour of this allocator is verified against the ad9361 driver by
 //! test.
@@ -18,69 +21,124 @@
 use core::mem::MaybeUninit;
 use core::ptr;
 
-static mut HEAP_START: *mut u32 = ptr::null_mut();
-static mut HEAP_TOP: *mut u32 = ptr::null_mut();
-static mut HEAP_PREVIOUS: *mut u32 = ptr::null_mut();
-static mut HEAP_END: *mut u32 = ptr::null_mut();
+/// Allocator state for one driver instance
+pub struct Heap {
+    start: *mut u32,
+    top: *mut u32,
+    previous: *mut u32,
+    end: *mut u32,
+    scratchpad: MaybeUninit<u64>,
+    scratchpad_allocated: u8,
+    high_water_mark: usize,
+    exhausted: bool,
+}
+
+impl Heap {
+    pub const fn new() -> Self {
+        Self {
+            start: ptr::null_mut(),
+            top: ptr::null_mut(),
+            previous: ptr::null_mut(),
+            end: ptr::null_mut(),
+            scratchpad: MaybeUninit::uninit(),
+            scratchpad_allocated: 0,
+            high_water_mark: 0,
+            exhausted: false,
+        }
+    }
+    /// Maximum number of words allocated from the heap at any one time since
+    /// the heap was initialised
+    pub fn high_water_mark(&self) -> usize {
+        self.high_water_mark
+    }
+    /// Returns true if an allocation has failed since the heap was
+    /// initialised
+    pub fn exhausted(&self) -> bool {
+        self.exhausted
+    }
+}
+
+// Static pointer to the heap of the instance currently calling the driver
+static mut HEAP: *mut Heap = ptr::null_mut();
 
-static mut SCRATCHPAD: MaybeUninit<[u8; 8]> = MaybeUninit::uninit();
-static mut SCRATCHPAD_ALLOCATED: u8 = 0;
+/// Make `heap` the target of subsequent allocations
+pub unsafe fn set_heap(heap: *mut Heap) {
+    HEAP = heap;
+}
 
 pub unsafe fn init_admalloc(heap_start: *mut u32, heap_len: usize) {
-    HEAP_START = heap_start;
-    HEAP_TOP = HEAP_START;
-    HEAP_END = heap_start.add(heap_len);
-    SCRATCHPAD_ALLOCATED = 0;
+    let heap = &mut *HEAP;
+    // Skip a word at the start if the heap is not aligned to 8 bytes
+    let skip = heap_start.align_offset(8).min(heap_len);
+    heap.start = heap_start.add(skip);
+    heap.top = heap.start;
+    heap.end = heap_start.add(heap_len);
+    heap.scratchpad_allocated = 0;
+    heap.high_water_mark = 0;
+    heap.exhausted = false;
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn admalloc(size: usize) -> *mut u32 {
+    assert!(!HEAP.is_null(), "AD936x: admalloc was not initialized");
+    let heap = &mut *HEAP;
+
     if size < 8 {
         // allocate from scratchpad
         debug_assert!(
-            SCRATCHPAD_ALLOCATED == 0,
+            heap.scratchpad_allocated == 0,
             "AD936x: attempt to double-allocate scratchpad"
         );
-        SCRATCHPAD_ALLOCATED = 1;
-        SCRATCHPAD.as_mut_ptr() as *mut _
+        heap.scratchpad_allocated = 1;
+        heap.scratchpad.as_mut_ptr() as *mut _
     } else {
         // allocate from heap
-        assert!(!HEAP_TOP.is_null(), "AD936x: admalloc was not initialized");
-
-        let words = (size + 3) / 4;
-        HEAP_PREVIOUS = HEAP_TOP;
-        HEAP_TOP = HEAP_TOP.add(words);
-        assert!(
-            HEAP_TOP.offset_from(HEAP_END) <= 0,
-            "AD936x: Heap exhausted, memory allocation failed"
-        );
+        assert!(!heap.top.is_null(), "AD936x: admalloc was not initialized");
+
+        let words = size.div_ceil(8) * 2;
+        if words as isize > heap.end.offset_from(heap.top) {
+            // Return NULL to the driver, which fails with -ENOMEM. Panicking
+            // here would unwind across the FFI boundary
+            warn!("AD936x: Heap exhausted, memory allocation failed");
+            heap.exhausted = true;
+            return ptr::null_mut();
+        }
+        heap.previous = heap.top;
+        heap.top = heap.top.add(words);
+
+        let used = heap.top.offset_from(heap.start) as usize;
+        heap.high_water_mark = heap.high_water_mark.max(used);
 
         debug!("AD936x: allocated {} bytes in {} words", size, words);
 
-        HEAP_PREVIOUS
+        heap.previous
     }
 }
 #[no_mangle]
 pub unsafe extern "C" fn adcalloc(nmemb: usize, size: usize) -> *mut u32 {
     let mem = admalloc(nmemb * size);
 
-    ptr::write_bytes(mem, 0, nmemb * size);
+    if !mem.is_null() {
+        ptr::write_bytes(mem, 0, nmemb * size);
+    }
     mem
 }
 #[no_mangle]
 pub unsafe extern "C" fn adfree(ptr: *mut u32) {
+    let heap = &mut *HEAP;
+
     if ptr.is_null() {
         warn!("AD936x: Tried to free null pointer");
-    } else if ptr == SCRATCHPAD.as_mut_ptr() as *mut _ {
-        SCRATCHPAD_ALLOCATED = 0;
-    } else if ptr == HEAP_START {
+    } else if ptr == heap.scratchpad.as_mut_ptr() as *mut _ {
+        heap.scratchpad_allocated = 0;
+    } else if ptr == heap.start {
         // deallocate everything
-        HEAP_TOP = HEAP_START;
+        heap.top = heap.start;
 
         debug!("AD936x: deallocated everything");
-    } else if ptr == HEAP_PREVIOUS {
+    } else if ptr == heap.previous {
         // deallocate last allocation
-        HEAP_TOP = HEAP_PREVIOUS;
+        heap.top = heap.previous;
 
         debug!("AD936x: deallocated last allocation");
     }
@@ -0,0 +1,117 @@
+//! Write coalescing
+//!
+//! Consecutive single register writes to adjacent registers are merged into
+//! one multi-byte write. In the default MSB-first mode the AD9361 decrements
+//! the register address after each byte of a multi-byte transfer, so a write
+//! to register `r` can be appended to a pending burst ending at `r + 1`.
+//!
+//! The pending burst is written before any other transfer, delay or GPIO
+//! change, so the order of operations seen by the device is unchanged.
+
+use core::ptr;
+
+use crate::bindings;
+use crate::transaction::Ad9361Transaction;
+
+/// Largest number of data bytes in a single transfer
+const MAX_BURST: usize = 8;
+
+/// Pending burst write of one driver instance
+pub struct WriteBatch {
+    pub enabled: bool,
+    descriptor: *mut bindings::spi_desc,
+    /// First (highest) register of the burst
+    register: u16,
+    data: [u8; MAX_BURST],
+    length: usize,
+}
+
+impl WriteBatch {
+    pub const fn new() -> Self {
+        Self {
+            enabled: false,
+            descriptor: ptr::null_mut(),
+            register: 0,
+            data: [0; MAX_BURST],
+            length: 0,
+        }
+    }
+    /// Write the pending burst, if any
+    pub fn flush(&mut self) -> i32 {
+        if self.length == 0 {
+            return 0;
+        }
+        let mut bytes = [0; 2 + MAX_BURST];
+        let [high, low] = self.register.to_be_bytes();
+        bytes[0] = 0x80 | (((self.length - 1) as u8) << 4) | (high & 0x3);
+        bytes[1] = low;
+        bytes[2..2 + self.length].copy_from_slice(&self.data[..self.length]);
+        let length = self.length;
+        self.length = 0;
+
+        super::transfer(self.descriptor, &mut bytes[..2 + length])
+    }
+    /// Add a transfer to the pending burst if possible. Returns `None` if it
+    /// was added, or otherwise the status of writing the pending burst
+    fn add(
+        &mut self,
+        descriptor: *mut bindings::spi_desc,
+        bytes: &[u8],
+    ) -> Option<i32> {
+        let single_write = bytes.len() == 3 && {
+            let transaction = Ad9361Transaction(bytes);
+            transaction.is_write() && bytes[0] & 0x70 == 0
+        };
+        if !single_write {
+            return Some(self.flush());
+        }
+        let register = Ad9361Transaction(bytes).register();
+
+        let adjacent = self.length > 0
+            && self.length < MAX_BURST
+            && self.descriptor == descriptor
+            && u32::from(register) + self.length as u32
+                == u32::from(self.register);
+        if !adjacent {
+            let status = self.flush();
+            if status != 0 {
+                return Some(status);
+            }
+            self.descriptor = descriptor;
+            self.register = register;
+        }
+        self.data[self.length] = bytes[2];
+        self.length += 1;
+        None
+    }
+}
+
+// Static pointer to the batch of the instance currently calling the driver
+static mut BATCH: *mut WriteBatch = ptr::null_mut();
+
+/// Make `batch` the target of subsequent SPI transfers
+pub unsafe fn set_batch(batch: *mut WriteBatch) {
+    BATCH = batch;
+}
+
+/// Add a transfer to the pending burst of the active instance, if write
+/// coalescing is enabled. Returns `None` if the transfer was added, or
+/// otherwise the status of writing the pending burst
+pub fn add(descriptor: *mut bindings::spi_desc, bytes: &[u8]) -> Option<i32> {
+    unsafe {
+        if BATCH.is_null() || !(*BATCH).enabled {
+            return Some(0);
+        }
+        (*BATCH).add(descriptor, bytes)
+    }
+}
+
+/// Write the pending burst of the active instance, if any
+pub fn flush() -> i32 {
+    unsafe {
+        if BATCH.is_null() {
+            return 0;
+        }
+        (*BATCH).flush()
+    }
+}
@@ -0,0 +1,175 @@
+//! Register shadow cache
+//!
+//! Values written to the AD9361 are recorded, and later reads of those
+//! registers are answered from the cache without a SPI transaction. Registers
+//! that are never written are always read from the device, as are the
+//! registers that the device itself changes, see [`VOLATILE`].
+
+use core::ptr;
+
+use crate::registers::*;
+use crate::transaction::Ad9361Transaction;
+
+/// Number of registers in the AD9361 address space
+const REGISTERS: usize = 1024;
+
+/// Registers whose contents are changed by the device: self-clearing control
+/// bits, read-only status, and write-one-to-clear flags. They are never
+/// cached, even after a write
+const VOLATILE: [u16; 22] = [
+    REG_SPI_CONF,                 // soft reset
+    REG_START_TEMP_READING,       // self-clearing
+    REG_TEMPERATURE,              // read-only
+    REG_CALIBRATION_CTRL,         // self-clearing
+    REG_STATE,                    // read-only
+    REG_AUXADC_WORD_MSB,          // read-only
+    REG_AUXADC_WORD_LSB,          // read-only
+    REG_PRODUCT_ID,               // read-only
+    REG_CH_1_OVERFLOW,            // write-one-to-clear, BBPLL lock
+    REG_CH_2_OVERFLOW,            // write-one-to-clear
+    REG_GAIN_TABLE_READ_DATA1,    // read-only
+    REG_GAIN_TABLE_READ_DATA2,    // read-only
+    REG_GAIN_TABLE_READ_DATA3,    // read-only
+    REG_GAIN_TABLE_CONFIG,        // self-clearing START_GAIN_TABLE_CLOCK
+    REG_RSSI_CONFIG,              // self-clearing START_RSSI_MEAS
+    REG_RX_CAL_STATUS,            // read-only
+    REG_RX_CP_OVERRANGE_VCO_LOCK, // read-only
+    REG_TX_CAL_STATUS,            // read-only
+    REG_TX_CP_OVERRANGE_VCO_LOCK, // read-only
+    REG_TX_RSSI1,                 // read-only
+    REG_TX_RSSI2,                 // read-only
+    REG_TX_RSSI_LSB,              // read-only
+];
+
+/// Shadow copy of the registers written by one driver instance
+pub struct RegisterCache {
+    values: [u8; REGISTERS],
+    valid: [u32; REGISTERS / 32],
+}
+
+impl RegisterCache {
+    pub const fn new() -> Self {
+        Self {
+            values: [0; REGISTERS],
+            valid: [0; REGISTERS / 32],
+        }
+    }
+    /// Forget all cached values
+    pub fn invalidate(&mut self) {
+        self.valid = [0; REGISTERS / 32];
+    }
+    fn get(&self, register: u16) -> Option<u8> {
+        let r = register as usize;
+        if self.valid[r / 32] & (1 << (r % 32)) != 0 {
+            Some(self.values[r])
+        } else {
+            None
+        }
+    }
+    fn set(&mut self, register: u16, value: u8) {
+        let r = register as usize;
+        if VOLATILE.contains(&register) {
+            self.valid[r / 32] &= !(1 << (r % 32));
+        } else {
+            self.values[r] = value;
+            self.valid[r / 32] |= 1 << (r % 32);
+        }
+    }
+    /// Answer a read transaction from the cache. Returns false, leaving
+    /// `data` unchanged, unless every register read is cached
+    fn read(&self, data: &mut [u8]) -> bool {
+        let transaction = Ad9361Transaction(data);
+        if transaction.is_write() {
+            return false;
+        }
+        let register = transaction.register();
+        let length = transaction.length().min(data.len() - 2);
+
+        if (0..length).any(|i| self.get(address(register, i)).is_none()) {
+            return false;
+        }
+        for i in 0..length {
+            data[2 + i] = self.values[address(register, i) as usize];
+        }
+        true
+    }
+    /// Record the values of a write transaction
+    fn write(&mut self, data: &[u8]) {
+        let transaction = Ad9361Transaction(data);
+        if !transaction.is_write() {
+            return;
+        }
+        let register = transaction.register();
+        let length = transaction.length().min(data.len() - 2);
+
+        for i in 0..length {
+            self.set(address(register, i), data[2 + i]);
+        }
+    }
+}
+
+/// Address of the `i`th byte of a transfer starting at `register`.
+/// Multi-byte transfers decrement the address
+fn address(register: u16, i: usize) -> u16 {
+    register.wrapping_sub(i as u16) & (REGISTERS as u16 - 1)
+}
+
+// Static pointer to the cache of the instance currently calling the driver
+static mut CACHE: *mut RegisterCache = ptr::null_mut();
+
+/// Make `cache` the target of subsequent SPI transfers
+pub unsafe fn set_cache(cache: *mut RegisterCache) {
+    CACHE = cache;
+}
+
+/// Answer a read transaction from the cache of the active instance, if
+/// possible. Returns true if `data` was filled from the cache
+pub fn cached_read(data: &mut [u8]) -> bool {
+    unsafe { !CACHE.is_null() && (*CACHE).read(data) }
+}
+
+/// Record a completed write transaction in the cache of the active instance
+pub fn record_write(data: &[u8]) {
+    unsafe {
+        if !CACHE.is_null() {
+            (*CACHE).write(data);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A write transaction of `value` to `register`
+    fn write(register: u16, value: u8) -> [u8; 3] {
+        [0x80 | (register >> 8) as u8, register as u8, value]
+    }
+    /// A read transaction of `register`
+    fn read(register: u16) -> [u8; 3] {
+        [(register >> 8) as u8, register as u8, 0]
+    }
+
+    #[test]
+    fn cached_after_write() {
+        let mut cache = RegisterCache::new();
+        assert!(!cache.read(&mut read(REG_TEMP_OFFSET)));
+
+        cache.write(&write(REG_TEMP_OFFSET, 0x12));
+        let mut data = read(REG_TEMP_OFFSET);
+        assert!(cache.read(&mut data));
+        assert_eq!(data[2], 0x12);
+
+        cache.invalidate();
+        assert!(!cache.read(&mut read(REG_TEMP_OFFSET)));
+    }
+
+    #[test]
+    fn volatile_never_cached() {
+        let mut cache = RegisterCache::new();
+        for register in VOLATILE {
+            cache.write(&write(register, 0x01));
+            assert!(!cache.read(&mut read(register)), "{:#05x}", register);
+        }
+    }
+}
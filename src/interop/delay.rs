@@ -3,43 +3,61 @@
 use core::mem;
 use core::ptr;
 
-use embedded_hal::blocking;
+use crate::hal;
 
 // During initialisation, we create pointers to the specialised versions of
 // these wrapper methods
 
 /// Wrapper method for millisecond delay
-pub fn delay_ms_method<DELAY: blocking::delay::DelayMs<u32>>(
-    outer: &mut DELAY,
-    delay: u32,
-) {
+pub fn delay_ms_method<DELAY: hal::Delay>(outer: &mut DELAY, delay: u32) {
     outer.delay_ms(delay);
 }
 /// Wrapper method for microsecond delay
-pub fn delay_us_method<DELAY: blocking::delay::DelayUs<u32>>(
-    outer: &mut DELAY,
-    delay: u32,
-) {
+pub fn delay_us_method<DELAY: hal::Delay>(outer: &mut DELAY, delay: u32) {
     outer.delay_us(delay);
 }
 
-// Static pointers to the most recently initialised delay object
+/// Delay methods for one driver instance
+pub struct Delay {
+    pub us: *mut (),
+    pub ms: *mut (),
+    pub object: *mut (),
+}
+
+impl Delay {
+    pub const fn new() -> Self {
+        Self {
+            us: ptr::null_mut(),
+            ms: ptr::null_mut(),
+            object: ptr::null_mut(),
+        }
+    }
+}
 
-pub static mut DELAY_US: *mut () = ptr::null_mut();
-pub static mut DELAY_MS: *mut () = ptr::null_mut();
-pub static mut DELAY_OBJECT: *mut () = ptr::null_mut();
+// Static pointer to the delay of the instance currently calling the driver
+static mut DELAY: *const Delay = ptr::null();
+
+/// Make `delay` the target of subsequent delay calls
+pub unsafe fn set_delay(delay: *const Delay) {
+    DELAY = delay;
+}
 
 /// void mdelay(uint32_t msecs);
 #[no_mangle]
 pub extern "C" fn mdelay(delay: u32) {
     trace!("delay_ms! {}", delay);
+    if super::batch::flush() != 0 {
+        warn!("AD936x: failed to write pending registers before delay");
+    }
 
     unsafe {
-        assert!(!DELAY_MS.is_null());
-        assert!(!DELAY_OBJECT.is_null());
+        assert!(!DELAY.is_null());
+        let d = &*DELAY;
+        assert!(!d.ms.is_null());
+        assert!(!d.object.is_null());
 
-        let method: fn(&mut (), u32) = mem::transmute(DELAY_MS);
-        method(&mut *DELAY_OBJECT, delay);
+        let method: fn(&mut (), u32) = mem::transmute(d.ms);
+        method(&mut *d.object, delay);
     }
 }
 
@@ -47,12 +65,17 @@ pub extern "C" fn mdelay(delay: u32) {
 #[no_mangle]
 pub extern "C" fn udelay(delay: u32) {
     trace!("delay_us! {}", delay);
+    if super::batch::flush() != 0 {
+        warn!("AD936x: failed to write pending registers before delay");
+    }
 
     unsafe {
-        assert!(!DELAY_US.is_null());
-        assert!(!DELAY_OBJECT.is_null());
+        assert!(!DELAY.is_null());
+        let d = &*DELAY;
+        assert!(!d.us.is_null());
+        assert!(!d.object.is_null());
 
-        let method: fn(&mut (), u32) = mem::transmute(DELAY_US);
-        method(&mut *DELAY_OBJECT, delay);
+        let method: fn(&mut (), u32) = mem::transmute(d.us);
+        method(&mut *d.object, delay);
     }
 }
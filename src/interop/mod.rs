@@ -3,9 +3,9 @@
 use core::mem;
 use core::ptr;
 use core::slice;
+use core::sync::atomic::{AtomicPtr, Ordering};
 
-use crate::bindings;
-use embedded_hal::{blocking, digital};
+use crate::{bindings, hal};
 
 mod alloc;
 pub use alloc::*;
@@ -13,11 +13,118 @@ pub use alloc::*;
 mod delay;
 pub use delay::*;
 
+mod batch;
+pub use batch::WriteBatch;
+
 mod print;
 
-mod errno {
+#[cfg(feature = "register_cache")]
+mod cache;
+#[cfg(feature = "register_cache")]
+pub use cache::RegisterCache;
+
+/// Per-instance state used by the C driver's platform functions
+pub struct Context {
+    pub heap: Heap,
+    pub delay: Delay,
+    pub batch: WriteBatch,
+    #[cfg(feature = "register_cache")]
+    pub cache: RegisterCache,
+}
+
+impl Context {
+    pub const fn new() -> Self {
+        Self {
+            heap: Heap::new(),
+            delay: Delay::new(),
+            batch: WriteBatch::new(),
+            #[cfg(feature = "register_cache")]
+            cache: RegisterCache::new(),
+        }
+    }
+}
+
+// Context of the instance currently using the C driver, or null
+static OWNER: AtomicPtr<Context> = AtomicPtr::new(ptr::null_mut());
+
+/// Use of the C driver by one context, released when dropped
+pub struct Active {
+    release: bool,
+}
+impl Drop for Active {
+    fn drop(&mut self) {
+        if self.release {
+            OWNER.store(ptr::null_mut(), Ordering::Release);
+        }
+    }
+}
+
+/// Claim the driver for `context` if it is free. Returns the previous owner
+#[cfg(not(feature = "critical-section"))]
+fn claim(context: *mut Context) -> Result<*mut Context, *mut Context> {
+    OWNER.compare_exchange(
+        ptr::null_mut(),
+        context,
+        Ordering::Acquire,
+        Ordering::Relaxed,
+    )
+}
+/// Claim the driver for `context` if it is free. Returns the previous owner
+///
+/// The claim is made in a critical section, for targets without atomic
+/// compare-and-swap, or where the driver may be called from an interrupt
+#[cfg(feature = "critical-section")]
+fn claim(context: *mut Context) -> Result<*mut Context, *mut Context> {
+    critical_section::with(|_| {
+        let owner = OWNER.load(Ordering::Acquire);
+        if owner.is_null() {
+            OWNER.store(context, Ordering::Relaxed);
+            Ok(owner)
+        } else {
+            Err(owner)
+        }
+    })
+}
+
+/// Make `context` the target of subsequent allocations and delays, until the
+/// returned guard is dropped
+///
+/// The guard must be held across each call into the C driver, so that several
+/// driver instances can coexist. The C driver is not re-entrant, and its
+/// platform functions use global pointers to the active context. Returns
+/// `None` if another context is using the driver. Nested calls from the same
+/// context are allowed
+pub unsafe fn activate(context: *mut Context) -> Option<Active> {
+    let release = match claim(context) {
+        Ok(_) => true,
+        Err(owner) if owner == context => false,
+        Err(_) => return None,
+    };
+    set_heap(&mut (*context).heap);
+    set_delay(&(*context).delay);
+    batch::set_batch(&mut (*context).batch);
+    #[cfg(feature = "register_cache")]
+    cache::set_cache(&mut (*context).cache);
+
+    Some(Active { release })
+}
+
+pub(crate) mod errno {
     // Simple implementation of errno
     static ERRNO: cty::c_int = 0;
+
+    // Error numbers used by the no-OS drivers
+    pub(crate) const EIO: i32 = 5;
+    pub(crate) const ENOMEM: i32 = 12;
+    pub(crate) const EBUSY: i32 = 16;
+    pub(crate) const ENODEV: i32 = 19;
+    pub(crate) const EINVAL: i32 = 22;
+    // ETIMEDOUT differs between the Linux and newlib C libraries
+    #[cfg(target_os = "linux")]
+    pub(crate) const ETIMEDOUT: i32 = 110;
+    #[cfg(not(target_os = "linux"))]
+    pub(crate) const ETIMEDOUT: i32 = 116;
+
     #[no_mangle]
     pub extern "C" fn __errno() -> *const cty::c_int {
         &ERRNO
@@ -45,11 +152,17 @@ cpp! {{
 /// Calculate the quotient and the remainder of an integer division.
 ///
 /// The quotient is placed in the first parameter, the remainder is returned.
+/// Division by zero leaves the first parameter unchanged and returns zero.
 ///
 /// extern "C" uint64_t do_div(uint64_t* n,
 ///   uint64_t base);
 #[no_mangle]
 pub extern "C" fn do_div(n: *mut u64, base: u64) -> u64 {
+    if base == 0 {
+        warn!("AD936x: do_div called with zero divisor");
+        return 0;
+    }
+
     unsafe {
         let modulus: u64 = *n % base;
         *n /= base;
@@ -68,6 +181,10 @@ pub extern "C" fn do_div(n: *mut u64, base: u64) -> u64 {
 /// */
 /// int32_t ad9361_dig_tune(struct ad9361_rf_phy *phy, uint32_t max_freq,
 ///   enum dig_tune_flags flags)
+///
+/// The tuning needs the PRBS checker in the baseband processor, which the C
+/// driver cannot reach, so it is skipped during initialisation. Call
+/// `Ad9361::dig_tune` afterwards instead
 #[no_mangle]
 pub extern "C" fn ad9361_dig_tune(
     _phy: bindings::ad9361_rf_phy,
@@ -83,11 +200,8 @@ pub extern "C" fn ad9361_dig_tune(
 ///
 /// During initialisation, we create pointers to the specialised versions of
 /// this wrapper method
-pub fn spi_wr_method<SPI: blocking::spi::Transfer<u8>>(
-    outer: &mut SPI,
-    data: &mut [u8],
-) -> i32 {
-    match outer.transfer(data) {
+pub fn spi_wr_method<SPI: hal::Spi>(outer: &mut SPI, data: &mut [u8]) -> i32 {
+    match outer.transfer_in_place(data) {
         Ok(_) => 0,
         Err(_) => -1,
     }
@@ -120,21 +234,44 @@ pub extern "C" fn spi_write_and_read(
     data: *mut u8,
     number_of_bytes: u16,
 ) -> i32 {
+    let bytes =
+        unsafe { slice::from_raw_parts_mut(data, number_of_bytes as usize) };
+
+    match batch::add(descriptor, bytes) {
+        None => return 0, // Added to the pending burst
+        Some(0) => {}
+        Some(status) => return status,
+    }
+
+    transfer(descriptor, bytes)
+}
+
+/// Transfer `bytes` with the SPI peripheral of `descriptor`
+fn transfer(descriptor: *mut bindings::spi_desc, bytes: &mut [u8]) -> i32 {
     // Unpack
-    let (f_ptr, slf, bytes) = unsafe {
+    let (f_ptr, slf) = unsafe {
         // Function Pointer
         let f_ptr: fn(&mut (), &mut [u8]) -> i32 =
             mem::transmute((*descriptor).platform_ops);
         // Self
         let slf: &mut () = &mut *((*descriptor).extra as *mut _);
-        // Slice
-        let bytes = slice::from_raw_parts_mut(data, number_of_bytes as usize);
 
-        (f_ptr, slf, bytes)
+        (f_ptr, slf)
     };
 
+    #[cfg(feature = "register_cache")]
+    if cache::cached_read(bytes) {
+        return 0;
+    }
+
     // Call function pointer
-    f_ptr(slf, bytes)
+    let status = f_ptr(slf, bytes);
+
+    #[cfg(feature = "register_cache")]
+    if status == 0 {
+        cache::record_write(bytes);
+    }
+    status
 }
 
 /// int32_t spi_remove(struct spi_desc *desc);
@@ -149,20 +286,24 @@ pub extern "C" fn spi_remove(_descriptor: *mut bindings::spi_desc) -> i32 {
 ///
 /// During initialisation, we create pointers to the specialised versions of
 /// this wrapper method
-pub fn gpio_set_method<GPIO: digital::v2::OutputPin>(
+pub fn gpio_set_method<GPIO: hal::OutputPin + ?Sized>(
     outer: &mut GPIO,
     value: u8,
 ) -> i32 {
-    let result = match value {
-        0 => outer.set_low(),
-        _ => outer.set_high(),
-    };
-    match result {
+    match outer.set_state(value != 0) {
         Ok(_) => 0,
         Err(_) => -1,
     }
 }
 
+/// Wrapper method for GPIO output calls to a pin passed as a trait object
+pub fn gpio_set_dyn_method(
+    outer: &mut &mut dyn hal::OutputPin,
+    value: u8,
+) -> i32 {
+    gpio_set_method(*outer, value)
+}
+
 /// int32_t gpio_get(struct gpio_desc **desc,
 ///   const struct gpio_init_param *param);
 #[no_mangle]
@@ -245,6 +386,10 @@ pub extern "C" fn gpio_set_value(
         trace!("set_value! {} = {} (unconnected)", descriptor.number, value);
         0
     } else {
+        let status = batch::flush();
+        if status != 0 {
+            return status;
+        }
         trace!("set_value! {} = {}", descriptor.number, value);
         f_ptr(slf, value)
     }
@@ -261,8 +406,10 @@ pub extern "C" fn gpio_get_value(
 
     trace!("get_value! {}", descriptor.number);
 
+    // All the GPIOs used by the AD9361 driver are outputs, which cannot be
+    // read back
     unsafe {
-        (*value) = 0; // Not implemented
+        (*value) = 0;
     }
     0
 }
@@ -0,0 +1,50 @@
+//! Signal path latency
+
+/// A linear-phase filter stage in the RX or TX signal path
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct FilterStage {
+    /// Number of taps
+    pub taps: u32,
+    /// Decimation (RX) or interpolation (TX) ratio
+    pub ratio: u32,
+}
+
+/// Group delay (in samples at the data rate) of a chain of filter stages
+///
+/// `stages` are ordered from the converter (ADC or DAC) towards the data
+/// interface. Each stage delays the signal by (taps - 1) / 2 samples at its
+/// higher rate, which is the rate on the converter side. The result is
+/// rounded to the nearest sample.
+pub(crate) fn group_delay_samples(stages: &[FilterStage]) -> u32 {
+    // Accumulate twice the delay in converter clocks, to remain integer
+    let mut ratio = 1;
+    let mut delay_x2 = 0;
+    for stage in stages {
+        delay_x2 += (stage.taps - 1) * ratio;
+        ratio *= stage.ratio;
+    }
+
+    (delay_x2 + ratio) / (2 * ratio)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn single_stage() {
+        let fir = FilterStage { taps: 65, ratio: 1 };
+        assert_eq!(group_delay_samples(&[fir]), 32);
+    }
+
+    #[test]
+    fn decimating_chain() {
+        // 47 taps at 4x the data rate, then 11 taps at 2x
+        let stages = [
+            FilterStage { taps: 47, ratio: 2 },
+            FilterStage { taps: 11, ratio: 2 },
+        ];
+        // (23 + 5 * 2) / 4 = 8.25
+        assert_eq!(group_delay_samples(&stages), 8);
+    }
+}
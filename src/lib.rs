@@ -4,19 +4,20 @@
 //! # Usage
 //!
 //! ```
-//! use ad9361_rs::{Ad9361, Ad9361InitParam};
+//! use ad9361_rs::{Ad9361, Ad9361InitParam, MIN_HEAP_WORDS};
 //! use embedded_hal::blocking::spi::Transfer;
 //! use embedded_hal::digital::v2::OutputPin;
 //! use embedded_hal::blocking::delay::{DelayMs, DelayUs};
 //!
 //! fn example(spi: impl Transfer<u8>,
 //!            delay: impl DelayMs<u32> + DelayUs<u32>,
-//!            reset_n: impl OutputPin)
+//!            reset_n: impl OutputPin,
+//!            sync: Option<impl OutputPin>)
 //! {
 //!     let parameters: Ad9361InitParam = Default::default();
-//!     let heap = Vec::with_capacity(540);
+//!     let heap = Vec::with_capacity(MIN_HEAP_WORDS);
 //!
-//!     let mut ad9361 = Ad9361::new(spi, delay, Some(reset_n), heap); // ad9361 must not be moved after this point
+//!     let mut ad9361 = Ad9361::new(spi, delay, Some(reset_n), sync, heap); // ad9361 must not be moved after this point
 //!     ad9361.init(parameters).unwrap();
 //!
 //!     let _temperature = ad9361.get_temperature().unwrap();
@@ -26,20 +27,54 @@
 //! ## Split gain mode
 //!
 //! ```ignore
-//!     let mut ad9361 = ... // see example above
-//!     let mut parameters: Ad9361InitParam = Default::default();
-//!     parameters.set_gc_dig_gain_enable(1).set_split_gain_table_mode_enable(1);
-//!     ad9361.init(parameters).unwrap();
-//!
+//!     // the gain table is borrowed by the driver, so it must outlive ad9361
 //!     let mut gt = GainTable::new_from_recommended(
 //!         GainTableKind::Split,
 //!         2_000_000_000, // frequency (Hz), approximate
 //!     );
 //!
+//!     let mut ad9361 = ... // see example above
+//!     let mut parameters: Ad9361InitParam = Default::default();
+//!     parameters.set_gc_dig_gain_enable(1).set_split_gain_table_mode_enable(1);
+//!     ad9361.init(parameters).unwrap();
+//!
 //!     ad9361.set_gain_table(&mut gt).unwrap();
 //! }
 //! ```
 //!
+//! # embedded-hal 1.0
+//!
+//! By default the driver uses the embedded-hal 0.2 traits. Enable the `eh1`
+//! feature to use the embedded-hal 1.0 `SpiDevice`, `DelayNs` and
+//! `OutputPin` traits instead, see [`hal`].
+//!
+//! ```toml
+//! ad9361-rs = { features = ["eh1"] }
+//! ```
+//!
+//! # 16-bit SPI
+//!
+//! A SPI peripheral configured for 16-bit words can be used by wrapping it in
+//! [`hal::Spi16`] before passing it to `Ad9361::new`.
+//!
+//! # Chip select
+//!
+//! If the chip select is not driven by the SPI peripheral, for example on a
+//! shared bus, pair the peripheral with an output pin using [`hal::SpiCs`].
+//!
+//! # Preemption
+//!
+//! The C driver is not re-entrant. Each call into it claims the driver for the
+//! calling instance until the call returns, and a call on another instance in
+//! the meantime, from another thread or an interrupt, returns
+//! `Err(Ad9361Error::Busy)`.
+//!
+//! The claim uses an atomic compare-and-swap. On targets without one, such as
+//! thumbv6m, enable the `critical-section` feature to make the claim in a
+//! critical section instead, using the [critical-section] crate.
+//!
+//! [critical-section]: https://docs.rs/critical-section
+//!
 //! # `#[no_std]`
 //!
 //! To use the crate in a no-std enviroment, specify `default-features = false`
@@ -77,13 +112,17 @@ mod macros;
 mod bindings;
 
 mod ad9361;
+mod error;
 mod fir;
 mod gain_table;
+pub mod hal;
 mod init;
 mod interop;
+mod latency;
+mod registers;
+mod synth;
 mod types;
 
-#[cfg(test)]
 mod transaction;
 
 #[cfg(all(feature = "ad9361_device", feature = "ad9364_device"))]
@@ -102,7 +141,10 @@ compile_error!("Must select one and device flag");
 
 // Exports
 pub use ad9361::*;
+pub use error::{
+    Ad9361Error, ClockError, FirError, GainEntryError, InitParamError,
+};
 pub use fir::*;
 pub use gain_table::*;
-pub use init::Ad9361InitParam;
+pub use init::{Ad9361InitParam, Ad9361InitParamBuilder};
 pub use types::*;
@@ -6,9 +6,9 @@ macro_rules! ad9361_method {
 
         paste! {
             $( #[doc=$doc] )*
-            pub fn $name(&mut self, $( $arg:$t ),*) -> Result<(), i32> {
+            pub fn $name(&mut self, $( $arg:$t ),*) -> Result<(), Ad9361Error> {
                 assert!(!self.inner.is_null(), "Must call init() method before accessing ad9361");
-                let inner_ptr = self.inner;
+                let (_active, inner_ptr) = self.inner_ptr()?;
                 $(
                     $( let $arg: $rust = $arg.into(); )*
                 )*
@@ -20,21 +20,24 @@ macro_rules! ad9361_method {
                 if status == 0 {
                     Ok(())
                 } else {
-                    Err(status)
+                    Err(status.into())
                 }
             }
         }
     };
     // Call a method, optionally with pass-by-value arguments, followed by a
     // pass-by-reference return value
-    (GET: $name:ident $(, $aux:ident : $t:ty )*;  $rust:ty $(> $intermediate:expr)* => $ret:ty
+    (GET: $name:ident $(, $aux:ident : $t:ty $(=> $auxrust:ty)* )*;  $rust:ty $(> $intermediate:expr)* => $ret:ty
      $(; $doc:expr)* ) => {
 
         paste! {
             $( #[doc=$doc] )*
-            pub fn $name(&self, $( $aux:$t ),*) -> Result<$ret, i32> {
+            pub fn $name(&self, $( $aux:$t ),*) -> Result<$ret, Ad9361Error> {
                 assert!(!self.inner.is_null(), "Must call init() method before accessing ad9361");
-                let inner_ptr = self.inner;
+                let (_active, inner_ptr) = self.inner_ptr()?;
+                $(
+                    $( let $aux: $auxrust = $aux.into(); )*
+                )*
                 let mut result: $rust = Default::default();
                 let result_ptr = &mut result;
 
@@ -46,7 +49,7 @@ macro_rules! ad9361_method {
                     $( let result = $intermediate::from(result); )*
                     Ok(result.into())
                 } else {
-                    Err(status)
+                    Err(status.into())
                 }
             }
         }
@@ -60,7 +63,8 @@ macro_rules! ad9361_method {
             $( #[doc=$doc] )*
             pub fn $name(&self, $( $aux:$t ),*) -> $ret {
                 assert!(!self.inner.is_null(), "Must call init() method before accessing ad9361");
-                let inner_ptr = self.inner;
+                let (_active, inner_ptr) = self.inner_ptr()
+                    .expect("AD936x: driver is in use by another instance");
                 let mut result: $rust = Default::default();
                 let result_ptr = &mut result;
 
@@ -81,7 +85,8 @@ macro_rules! ad9361_method {
             $( #[doc=$doc] )*
             pub fn $name(&self, $( $aux:$t ),*) -> $ret {
                 assert!(!self.inner.is_null(), "Must call init() method before accessing ad9361");
-                let inner_ptr = self.inner;
+                let (_active, inner_ptr) = self.inner_ptr()
+                    .expect("AD936x: driver is in use by another instance");
                 let retval = unsafe {
                     bindings::[< ad9361_ $name >](inner_ptr, $( $aux, )*)
                 };
@@ -91,13 +96,13 @@ macro_rules! ad9361_method {
             }
         }
     };
-    (GET_SET: $name:ident $(, $aux:ident:$t:ty )*; $arg:ty $(> $intermediate:expr)* => $rust:ty
+    (GET_SET: $name:ident $(, $aux:ident:$t:ty $(=> $auxrust:ty)* )*; $arg:ty $(> $intermediate:expr)* => $rust:ty
         $(; $doc:expr)* ) => {
 
         paste! {
-            ad9361_method!(SET: [< set_ $name >]; $( $aux:$t, )* arg: $arg => $rust
+            ad9361_method!(SET: [< set_ $name >]; $( $aux:$t $(=> $auxrust)*, )* arg: $arg => $rust
                            $(; concat!("Set the ", $doc) )*);
-            ad9361_method!(GET: [< get_ $name >] $(, $aux:$t )*; $rust $(> $intermediate)* => $arg
+            ad9361_method!(GET: [< get_ $name >] $(, $aux:$t $(=> $auxrust)* )*; $rust $(> $intermediate)* => $arg
                            $(; concat!("Get the ", $doc) )*);
 
         }
@@ -0,0 +1,159 @@
+//! AD9361 register map
+//!
+//! Register addresses and bit fields, named as in the no-OS `ad9361.h`
+//! header. Only the registers accessed directly from Rust are listed here.
+
+// -------- SPI configuration --------
+pub(crate) const REG_SPI_CONF: u16 = 0x000;
+// The soft reset bit is mirrored, so it is set in either bit order
+pub(crate) const SOFT_RESET: u8 = (1 << 7) | (1 << 0);
+
+// -------- Operating mode --------
+pub(crate) const REG_TX_ENABLE_FILTER_CTRL: u16 = 0x002;
+pub(crate) const REG_RX_ENABLE_FILTER_CTRL: u16 = 0x003;
+pub(crate) const RX_CHANNEL_ENABLE_MASK: u8 = 0x3 << 6;
+// Filter enables, common to the TX and RX registers
+pub(crate) const FIR_ENABLE_MASK: u8 = 0x3;
+pub(crate) const HB1_EN: u8 = 1 << 2;
+pub(crate) const HB2_EN: u8 = 1 << 3;
+pub(crate) const HB3_ENABLE_SHIFT: u8 = 4;
+pub(crate) const HB3_ENABLE_MASK: u8 = 0x3 << HB3_ENABLE_SHIFT;
+pub(crate) const REG_TX_FILTER_CONF: u16 = 0x065;
+pub(crate) const REG_RX_FILTER_CONFIG: u16 = 0x0F5;
+pub(crate) const FIR_NUM_TAPS_SHIFT: u8 = 5;
+pub(crate) const REG_ENSM_MODE: u16 = 0x013;
+pub(crate) const FDD_MODE: u8 = 1 << 0;
+pub(crate) const REG_ENSM_CONFIG_1: u16 = 0x014;
+pub(crate) const TO_ALERT: u8 = 1 << 0;
+pub(crate) const REG_STATE: u16 = 0x017;
+pub(crate) const ENSM_STATE_MASK: u8 = 0xF;
+
+// -------- Clock output --------
+pub(crate) const REG_BBPLL: u16 = 0x00A;
+pub(crate) const CLKOUT_ENABLE: u8 = 1 << 4;
+pub(crate) const CLKOUT_SELECT_SHIFT: u8 = 5;
+pub(crate) const CLKOUT_SELECT_MASK: u8 = 0x7 << CLKOUT_SELECT_SHIFT;
+
+// -------- TX monitor --------
+pub(crate) const REG_TX_RSSI1: u16 = 0x06B;
+pub(crate) const REG_TX_RSSI2: u16 = 0x06C;
+pub(crate) const REG_TX_RSSI_LSB: u16 = 0x06D;
+pub(crate) const TX_RSSI_2: u8 = 1 << 1;
+pub(crate) const TX_RSSI_1: u8 = 1 << 0;
+
+// -------- Bias --------
+pub(crate) const REG_BANDGAP_CONFIG0: u16 = 0x2A6;
+pub(crate) const MASTER_BIAS_TRIM_MASK: u8 = 0x1F;
+pub(crate) const REG_BANDGAP_CONFIG1: u16 = 0x2A8;
+pub(crate) const BANDGAP_TEMP_TRIM_MASK: u8 = 0x1F;
+
+// -------- Temperature sensor --------
+pub(crate) const REG_TEMP_OFFSET: u16 = 0x00B;
+pub(crate) const REG_START_TEMP_READING: u16 = 0x00C;
+pub(crate) const START_TEMP_READING: u8 = 1 << 0;
+pub(crate) const REG_TEMP_SENSE2: u16 = 0x00D;
+#[cfg(feature = "register_cache")]
+pub(crate) const REG_TEMPERATURE: u16 = 0x00E;
+pub(crate) const TEMP_SENSE_PERIODIC_ENABLE: u8 = 1 << 0;
+pub(crate) const MEASUREMENT_TIME_INTERVAL_SHIFT: u8 = 1;
+pub(crate) const MEASUREMENT_TIME_INTERVAL_MAX: u64 = 0x7F;
+
+// -------- Calibration --------
+#[cfg(feature = "register_cache")]
+pub(crate) const REG_CALIBRATION_CTRL: u16 = 0x016;
+#[cfg(feature = "register_cache")]
+pub(crate) const REG_RX_CAL_STATUS: u16 = 0x244;
+#[cfg(feature = "register_cache")]
+pub(crate) const REG_TX_CAL_STATUS: u16 = 0x284;
+
+// -------- Auxiliary ADC --------
+pub(crate) const REG_AUXADC_CONFIG: u16 = 0x01D;
+pub(crate) const AUX_ADC_POWER_DOWN: u8 = 1 << 0;
+pub(crate) const REG_AUXADC_WORD_MSB: u16 = 0x01E;
+pub(crate) const REG_AUXADC_WORD_LSB: u16 = 0x01F;
+pub(crate) const AUXADC_WORD_LSB_MASK: u8 = 0xF;
+
+// -------- Control outputs --------
+pub(crate) const REG_CTRL_OUTPUT_POINTER: u16 = 0x035;
+pub(crate) const REG_CTRL_OUTPUT_ENABLE: u16 = 0x036;
+
+// -------- General purpose outputs --------
+pub(crate) const REG_EXTERNAL_LNA_CTRL: u16 = 0x026;
+pub(crate) const GPO_MANUAL_SELECT: u8 = 1 << 4;
+pub(crate) const REG_GPO_FORCE_AND_INIT: u16 = 0x027;
+pub(crate) const GPO_MANUAL_CTRL_SHIFT: u8 = 4;
+
+// -------- Data interface --------
+pub(crate) const REG_PARALLEL_PORT_CONF_1: u16 = 0x010;
+pub(crate) const REG_PARALLEL_PORT_CONF_2: u16 = 0x011;
+pub(crate) const DELAY_RX_DATA_MASK: u8 = 0x3;
+pub(crate) const REG_PARALLEL_PORT_CONF_3: u16 = 0x012;
+pub(crate) const LVDS_MODE: u8 = 1 << 4;
+pub(crate) const REG_RX_CLOCK_DATA_DELAY: u16 = 0x006;
+pub(crate) const REG_TX_CLOCK_DATA_DELAY: u16 = 0x007;
+pub(crate) const REG_LVDS_BIAS_CTRL: u16 = 0x03C;
+pub(crate) const RX_ON_CHIP_TERM: u8 = 1 << 5;
+
+// -------- Baseband filter tuning --------
+pub(crate) const REG_TX_BBF_TUNE_DIVIDER: u16 = 0x0D6;
+pub(crate) const REG_TX_BBF_TUNE_MODE: u16 = 0x0D7;
+pub(crate) const REG_RX_BBF_TUNE_DIVIDE: u16 = 0x1F8;
+pub(crate) const REG_RX_BBF_TUNE_CONFIG: u16 = 0x1F9;
+// Bit 8 of the tune divider, in the mode/config register
+pub(crate) const BBF_TUNE_DIVIDER_MSB: u8 = 1 << 0;
+
+// -------- Gain table --------
+pub(crate) const REG_GAIN_TABLE_ADDRESS: u16 = 0x130;
+pub(crate) const REG_GAIN_TABLE_READ_DATA1: u16 = 0x134;
+pub(crate) const REG_GAIN_TABLE_READ_DATA2: u16 = 0x135;
+pub(crate) const REG_GAIN_TABLE_READ_DATA3: u16 = 0x136;
+pub(crate) const REG_GAIN_TABLE_CONFIG: u16 = 0x137;
+pub(crate) const WRITE_GAIN_TABLE: u8 = 1 << 2;
+pub(crate) const START_GAIN_TABLE_CLOCK: u8 = 1 << 1;
+// RECEIVER_SELECT: 1 = RX1, 2 = RX2, 3 = both
+pub(crate) const fn receiver_select(x: u8) -> u8 {
+    (x & 0x3) << 3
+}
+
+// -------- RSSI --------
+pub(crate) const REG_RSSI_CONFIG: u16 = 0x158;
+pub(crate) const START_RSSI_MEAS: u8 = 1 << 5;
+pub(crate) const RSSI_MODE_SELECT_SHIFT: u8 = 2;
+pub(crate) const RSSI_MODE_SELECT_MASK: u8 = 0x7 << RSSI_MODE_SELECT_SHIFT;
+// Restart mode in which a measurement is started by START_RSSI_MEAS
+pub(crate) const RSSI_MODE_SPI_WRITE: u8 = 4;
+
+// -------- RF DC offset --------
+pub(crate) const REG_RF_DC_OFFSET_ATTEN: u16 = 0x188;
+pub(crate) const RF_DC_OFFSET_ATTEN_MASK: u8 = 0x1F;
+
+// -------- RF synthesizers --------
+pub(crate) const REG_RFPLL_DIVIDERS: u16 = 0x005;
+pub(crate) const REG_RX_INTEGER_BYTE_0: u16 = 0x231;
+pub(crate) const REG_TX_INTEGER_BYTE_0: u16 = 0x271;
+// Offsets from REG_xX_INTEGER_BYTE_0
+pub(crate) const INTEGER_BYTE_1: u16 = 1;
+pub(crate) const FRACT_BYTE_0: u16 = 2;
+pub(crate) const FRACT_BYTE_1: u16 = 3;
+pub(crate) const FRACT_BYTE_2: u16 = 4;
+
+// -------- BIST --------
+pub(crate) const REG_BIST_CONFIG: u16 = 0x3F4;
+pub(crate) const BIST_ENABLE: u8 = 1 << 0;
+
+// -------- Status --------
+pub(crate) const REG_PRODUCT_ID: u16 = 0x037;
+pub(crate) const PRODUCT_ID_MASK: u8 = 0xF8;
+pub(crate) const PRODUCT_ID_9361: u8 = 0x08;
+pub(crate) const REV_MASK: u8 = 0x07;
+pub(crate) const REG_CH_1_OVERFLOW: u16 = 0x05E;
+pub(crate) const REG_CH_2_OVERFLOW: u16 = 0x05F;
+pub(crate) const BBPLL_LOCK: u8 = 1 << 7;
+pub(crate) const OVERFLOW_MASK: u8 = 0x7F;
+pub(crate) const REG_RX_CP_OVERRANGE_VCO_LOCK: u16 = 0x247;
+pub(crate) const REG_TX_CP_OVERRANGE_VCO_LOCK: u16 = 0x287;
+pub(crate) const VCO_LOCK: u8 = 1 << 1;
+
+// -------- Transmit attenuation --------
+pub(crate) const REG_TX2_DIG_ATTEN: u16 = 0x07C;
+pub(crate) const IMMEDIATELY_UPDATE_TPC_ATTEN: u8 = 1 << 6;
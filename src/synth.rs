@@ -0,0 +1,99 @@
+//! RF synthesizer (RFPLL) arithmetic
+
+use core::ops::RangeInclusive;
+
+/// Modulus of the RFPLL fractional-N divider
+pub(crate) const RFPLL_MODULUS: u64 = 8_388_593;
+
+/// Minimum and maximum frequency of the RF VCOs
+pub(crate) const MIN_VCO_FREQ_HZ: u64 = 6_000_000_000;
+pub(crate) const MAX_VCO_FREQ_HZ: u64 = 12_000_000_000;
+/// Range of the RX and TX LOs
+#[cfg(not(feature = "ad9363a_device"))]
+pub(crate) const LO_FREQ_RANGE_HZ: RangeInclusive<u64> =
+    70_000_000..=6_000_000_000;
+#[cfg(feature = "ad9363a_device")]
+pub(crate) const LO_FREQ_RANGE_HZ: RangeInclusive<u64> =
+    325_000_000..=3_800_000_000;
+/// Number of VCO divider settings, 2 to 128
+const VCO_DIVIDERS: u8 = 7;
+
+/// Calculate the LO frequency produced by the RFPLL
+///
+/// f_LO = f_ref * (integer + fract / RFPLL_MODULUS) / 2^(vco_div + 1)
+pub(crate) fn rfpll_frequency(
+    reference_hz: u64,
+    integer: u64,
+    fract: u64,
+    vco_div: u8,
+) -> u64 {
+    let vco = reference_hz * integer + reference_hz * fract / RFPLL_MODULUS;
+
+    vco >> (vco_div + 1)
+}
+
+/// Decode the LO frequency held in a fastlock profile
+///
+/// See `Ad9361::decode_rx_fastlock_profile` for the layout of the profile
+pub(crate) fn fastlock_profile_frequency(
+    reference_hz: u64,
+    values: &[u8; 16],
+) -> u64 {
+    let integer = values[0] as u64 | ((values[1] as u64 & 0x07) << 8);
+    let fract = values[2] as u64
+        | ((values[3] as u64) << 8)
+        | ((values[4] as u64 & 0x7F) << 16);
+    let vco_div = values[15] & 0x0F;
+
+    rfpll_frequency(reference_hz, integer, fract, vco_div)
+}
+
+/// VCO divider that the no-OS driver selects for a given LO frequency
+///
+/// This is the smallest divider that places the VCO strictly above
+/// [`MIN_VCO_FREQ_HZ`], as in `ad9361_calc_rfpll_int_divder`. Each divider
+/// setting covers one octave of LO frequency, so the band boundaries are at
+/// 3 GHz, 1.5 GHz, 750 MHz, 375 MHz, 187.5 MHz and 93.75 MHz, each boundary
+/// belonging to the band below it. Returns `None` if the frequency cannot be
+/// reached by any divider
+pub(crate) fn rfpll_vco_divider(lo_hz: u64) -> Option<u8> {
+    if lo_hz == 0 || lo_hz > MAX_VCO_FREQ_HZ / 2 {
+        return None;
+    }
+    (0..VCO_DIVIDERS).find(|div| lo_hz << (div + 1) > MIN_VCO_FREQ_HZ)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rfpll_2400mhz() {
+        // 40 MHz reference, VCO at 4.8 GHz divided by 2
+        assert_eq!(rfpll_frequency(40_000_000, 120, 0, 0), 2_400_000_000);
+    }
+
+    #[test]
+    fn vco_divider_bands() {
+        assert_eq!(rfpll_vco_divider(6_000_000_000), Some(0));
+        assert_eq!(rfpll_vco_divider(3_000_000_001), Some(0));
+        assert_eq!(rfpll_vco_divider(3_000_000_000), Some(1));
+        assert_eq!(rfpll_vco_divider(2_999_999_999), Some(1));
+        assert_eq!(rfpll_vco_divider(70_000_000), Some(6));
+        assert_eq!(rfpll_vco_divider(46_875_001), Some(6));
+        assert_eq!(rfpll_vco_divider(46_875_000), None);
+        assert_eq!(rfpll_vco_divider(6_000_000_001), None);
+    }
+
+    #[test]
+    fn fastlock_profile() {
+        // Integer = 0x078, Fract = 0, VCO divider = 1
+        let mut values = [0u8; 16];
+        values[0] = 0x78;
+        values[15] = 0x01;
+        assert_eq!(
+            fastlock_profile_frequency(40_000_000, &values),
+            1_200_000_000
+        );
+    }
+}
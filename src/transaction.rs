@@ -1,4 +1,5 @@
-/// Wrapper around a AD9361 Transaction
+//! Wrapper around a AD9361 Transaction
+
 use core::fmt;
 
 pub struct Ad9361Transaction<'a>(pub &'a [u8]);
@@ -10,12 +11,36 @@ impl<'a> Ad9361Transaction<'a> {
     pub fn is_write(&self) -> bool {
         self.0[0] & 0x80 > 0
     }
+    #[cfg(test)]
     pub fn value(&self) -> u8 {
         self.0[2]
     }
+    #[cfg(any(test, feature = "register_cache"))]
     pub fn length(&self) -> usize {
         ((self.0[0] >> 4) & 7) as usize + 1
     }
+    /// Pack the transaction into 16-bit words, most significant byte first.
+    /// A transaction with an odd number of bytes is padded with a trailing
+    /// zero byte. Returns the number of words used
+    pub fn to_words16(&self, words: &mut [u16]) -> usize {
+        let mut n = 0;
+        for (word, pair) in words.iter_mut().zip(self.0.chunks(2)) {
+            *word = u16::from_be_bytes([pair[0], *pair.get(1).unwrap_or(&0)]);
+            n += 1;
+        }
+        n
+    }
+    /// Unpack 16-bit words into `bytes`, most significant byte first. Any
+    /// padding byte beyond the end of `bytes` is dropped
+    pub fn from_words16(words: &[u16], bytes: &mut [u8]) {
+        for (pair, word) in bytes.chunks_mut(2).zip(words) {
+            let [msb, lsb] = word.to_be_bytes();
+            pair[0] = msb;
+            if let Some(b) = pair.get_mut(1) {
+                *b = lsb;
+            }
+        }
+    }
 }
 
 impl<'a> fmt::Debug for Ad9361Transaction<'a> {
@@ -30,3 +55,21 @@ impl<'a> fmt::Debug for Ad9361Transaction<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn words16_round_trip() {
+        // Single byte write of 0x5A to register 0x123
+        let bytes = [0x81, 0x23, 0x5A];
+        let mut words = [0u16; 2];
+        let n = Ad9361Transaction(&bytes).to_words16(&mut words);
+        assert_eq!(&words[..n], &[0x8123, 0x5A00]);
+
+        let mut unpacked = [0u8; 3];
+        Ad9361Transaction::from_words16(&words[..n], &mut unpacked);
+        assert_eq!(unpacked, bytes);
+    }
+}
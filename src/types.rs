@@ -119,6 +119,117 @@ impl From<u8> for EnsmState {
     }
 }
 
+/// Enable State Machine (ENSM) mode, see `set_en_state_machine_mode`
+#[derive(Clone, Copy, PartialOrd, PartialEq, Eq, Debug)]
+pub enum EnsmMode {
+    /// TDD, transmit
+    Tx,
+    /// TDD, receive
+    Rx,
+    /// Synthesizers enabled, signal chains disabled
+    Alert,
+    /// FDD, both signal chains enabled
+    Fdd,
+    /// Clocks enabled
+    Wait,
+    /// Clocks and BBPLL disabled
+    Sleep,
+    /// ENSM controlled by the ENABLE and TXNRX pins
+    PinCtrl,
+    /// ENSM controlled by the ENABLE and TXNRX pins, with the RX and TX signal
+    /// chains enabled independently in FDD
+    PinCtrlFddIndep,
+    /// Unrecognised mode value. This is rejected by the driver if set
+    Unknown(u32),
+}
+impl From<EnsmMode> for u32 {
+    fn from(mode: EnsmMode) -> u32 {
+        match mode {
+            EnsmMode::Tx => bindings::ENSM_MODE_TX,
+            EnsmMode::Rx => bindings::ENSM_MODE_RX,
+            EnsmMode::Alert => bindings::ENSM_MODE_ALERT,
+            EnsmMode::Fdd => bindings::ENSM_MODE_FDD,
+            EnsmMode::Wait => bindings::ENSM_MODE_WAIT,
+            EnsmMode::Sleep => bindings::ENSM_MODE_SLEEP,
+            EnsmMode::PinCtrl => bindings::ENSM_MODE_PINCTRL,
+            EnsmMode::PinCtrlFddIndep => bindings::ENSM_MODE_PINCTRL_FDD_INDEP,
+            EnsmMode::Unknown(v) => v,
+        }
+    }
+}
+impl From<u32> for EnsmMode {
+    fn from(v: u32) -> Self {
+        match v {
+            bindings::ENSM_MODE_TX => Self::Tx,
+            bindings::ENSM_MODE_RX => Self::Rx,
+            bindings::ENSM_MODE_ALERT => Self::Alert,
+            bindings::ENSM_MODE_FDD => Self::Fdd,
+            bindings::ENSM_MODE_WAIT => Self::Wait,
+            bindings::ENSM_MODE_SLEEP => Self::Sleep,
+            bindings::ENSM_MODE_PINCTRL => Self::PinCtrl,
+            bindings::ENSM_MODE_PINCTRL_FDD_INDEP => Self::PinCtrlFddIndep,
+            _ => Self::Unknown(v),
+        }
+    }
+}
+
+/// Number of active RX and TX channels
+#[derive(Clone, Copy, PartialOrd, PartialEq, Eq, Debug)]
+pub enum ChannelMode {
+    /// One receiver and one transmitter (1R1T)
+    OneByOne,
+    /// Two receivers and two transmitters (2R2T)
+    TwoByTwo,
+}
+impl From<ChannelMode> for u8 {
+    fn from(mode: ChannelMode) -> u8 {
+        match mode {
+            ChannelMode::OneByOne => bindings::MODE_1x1 as u8,
+            ChannelMode::TwoByTwo => bindings::MODE_2x2 as u8,
+        }
+    }
+}
+
+/// Receive channel, for methods taking a `channel: impl Into<u8>`
+///
+/// [`Rx2`](Self::Rx2) is only valid in 2R2T mode. In 1R1T mode the driver
+/// rejects it with an error
+#[derive(Clone, Copy, PartialOrd, PartialEq, Eq, Debug)]
+pub enum RxChannel {
+    /// RX1, channel 0
+    Rx1,
+    /// RX2, channel 1
+    Rx2,
+}
+impl From<RxChannel> for u8 {
+    fn from(channel: RxChannel) -> u8 {
+        match channel {
+            RxChannel::Rx1 => 0,
+            RxChannel::Rx2 => 1,
+        }
+    }
+}
+
+/// Transmit channel, for methods taking a `channel: impl Into<u8>`
+///
+/// [`Tx2`](Self::Tx2) is only valid in 2R2T mode. In 1R1T mode the driver
+/// rejects it with an error
+#[derive(Clone, Copy, PartialOrd, PartialEq, Eq, Debug)]
+pub enum TxChannel {
+    /// TX1, channel 0
+    Tx1,
+    /// TX2, channel 1
+    Tx2,
+}
+impl From<TxChannel> for u8 {
+    fn from(channel: TxChannel) -> u8 {
+        match channel {
+            TxChannel::Tx1 => 0,
+            TxChannel::Tx2 => 1,
+        }
+    }
+}
+
 /// Internal / External LO selection
 #[derive(Clone, Copy, PartialOrd, PartialEq, Eq, Debug)]
 pub enum InternalExternalLO {
@@ -185,6 +296,243 @@ impl From<bindings::ad9361_bist_mode> for BistMode {
     }
 }
 
+/// BIST tone configuration, see `get_bist_tone`
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct BistTone {
+    /// Injection point of the tone
+    pub mode: BistMode,
+    /// Tone frequency in Hz, as passed to `bist_tone`
+    pub frequency: u32,
+    /// Tone level in dB below full scale, as passed to `bist_tone`
+    pub level_db: u32,
+    /// Channel mask, as passed to `bist_tone`
+    pub mask: u32,
+    /// The BIST is enabled in the BIST configuration register
+    pub enabled: bool,
+}
+
+/// Outcome of a PRBS test, see `run_prbs_loopback_test`
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PrbsResult {
+    /// The PRBS checker never locked to the sequence
+    NeverLocked,
+    /// The PRBS checker locked, and counted `errors` bit errors
+    Locked { errors: u32 },
+}
+impl PrbsResult {
+    /// The checker locked without any bit errors
+    pub fn passed(&self) -> bool {
+        *self == PrbsResult::Locked { errors: 0 }
+    }
+}
+
+/// Radio settings that are switched at runtime, see `capture_profile` and
+/// `apply_profile`
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Ad9361Profile {
+    /// RX LO frequency in Hz
+    pub rx_lo_hz: u64,
+    /// TX LO frequency in Hz
+    pub tx_lo_hz: u64,
+    /// RX sampling rate in Hz. The TX sampling rate is derived from the same
+    /// BBPLL
+    pub sampling_hz: u32,
+    /// RX RF bandwidth in Hz
+    pub rx_bandwidth_hz: u32,
+    /// TX RF bandwidth in Hz
+    pub tx_bandwidth_hz: u32,
+    /// Gain control mode of RX1 and RX2
+    pub gain_control: [RfGainControlMode; 2],
+    /// Attenuation of TX1 and TX2 in mdB
+    pub tx_attenuation_mdb: [u32; 2],
+    /// The RX FIR filter is enabled
+    pub rx_fir_enabled: bool,
+    /// The TX FIR filter is enabled
+    pub tx_fir_enabled: bool,
+}
+
+/// Configuration of the parallel data port, see
+/// `set_digital_interface_mode`
+///
+/// The fields correspond to the parallel port fields of the initialisation
+/// parameters, without the `_enable` suffix. The LVDS or CMOS mode is set
+/// separately by `set_interface_electrical_mode`
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct DigitalInterfaceConfig {
+    /// Swap the I and Q samples of the TX port
+    pub pp_tx_swap: bool,
+    /// Swap the I and Q samples of the RX port
+    pub pp_rx_swap: bool,
+    /// Swap the TX1 and TX2 samples
+    pub tx_channel_swap: bool,
+    /// Swap the RX1 and RX2 samples
+    pub rx_channel_swap: bool,
+    /// RX_FRAME is a single pulse at the start of each burst, rather than a
+    /// 50% duty cycle signal
+    pub rx_frame_pulse_mode: bool,
+    /// Use the 2R2T frame timing when only one channel is enabled
+    pub two_t_two_r_timing: bool,
+    /// Reverse the bit order of the data bus
+    pub invert_data_bus: bool,
+    /// Invert DATA_CLK
+    pub invert_data_clk: bool,
+    /// Alternate the order of the TX and RX words in FDD mode
+    pub fdd_alt_word_order: bool,
+    /// Invert RX_FRAME
+    pub invert_rx_frame: bool,
+    /// RX sample rate is twice the TX sample rate in FDD mode
+    pub fdd_rx_rate_2tx: bool,
+    /// Swap the P0 and P1 ports
+    pub swap_ports: bool,
+    /// Single data rate rather than double data rate. CMOS only
+    pub single_data_rate: bool,
+    /// TX and RX share the port in half duplex mode. CMOS only
+    pub half_duplex: bool,
+    /// Use a single 12-bit port. CMOS only
+    pub single_port: bool,
+    /// Dedicated full width RX and TX ports
+    pub full_port: bool,
+    /// Swap the bits of the full duplex port
+    pub full_duplex_swap_bits: bool,
+}
+impl DigitalInterfaceConfig {
+    /// Values of the parallel port configuration registers 0x010 to 0x012,
+    /// with the LVDS mode and DELAY_RX_DATA bits clear
+    pub(crate) fn to_registers(self) -> [u8; 3] {
+        let bit = |enable: bool, shift: u8| u8::from(enable) << shift;
+        [
+            bit(self.pp_tx_swap, 7)
+                | bit(self.pp_rx_swap, 6)
+                | bit(self.tx_channel_swap, 5)
+                | bit(self.rx_channel_swap, 4)
+                | bit(self.rx_frame_pulse_mode, 3)
+                | bit(self.two_t_two_r_timing, 2)
+                | bit(self.invert_data_bus, 1)
+                | bit(self.invert_data_clk, 0),
+            bit(self.fdd_alt_word_order, 7) | bit(self.invert_rx_frame, 2),
+            bit(self.fdd_rx_rate_2tx, 7)
+                | bit(self.swap_ports, 6)
+                | bit(self.single_data_rate, 5)
+                | bit(self.half_duplex, 3)
+                | bit(self.single_port, 2)
+                | bit(self.full_port, 1)
+                | bit(self.full_duplex_swap_bits, 0),
+        ]
+    }
+    /// Decode the parallel port configuration registers 0x010 to 0x012
+    pub(crate) fn from_registers(conf: [u8; 3]) -> Self {
+        let bit =
+            |register: usize, shift: u8| conf[register] & (1 << shift) != 0;
+        Self {
+            pp_tx_swap: bit(0, 7),
+            pp_rx_swap: bit(0, 6),
+            tx_channel_swap: bit(0, 5),
+            rx_channel_swap: bit(0, 4),
+            rx_frame_pulse_mode: bit(0, 3),
+            two_t_two_r_timing: bit(0, 2),
+            invert_data_bus: bit(0, 1),
+            invert_data_clk: bit(0, 0),
+            fdd_alt_word_order: bit(1, 7),
+            invert_rx_frame: bit(1, 2),
+            fdd_rx_rate_2tx: bit(2, 7),
+            swap_ports: bit(2, 6),
+            single_data_rate: bit(2, 5),
+            half_duplex: bit(2, 3),
+            single_port: bit(2, 2),
+            full_port: bit(2, 1),
+            full_duplex_swap_bits: bit(2, 0),
+        }
+    }
+}
+
+/// Options for `dig_tune`
+///
+/// Values match `enum dig_tune_flags` in the no-OS `ad9361.h`
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct DigTuneFlags(u8);
+impl DigTuneFlags {
+    /// Log the pass/fail map of each delay sweep at debug level
+    pub const BE_VERBOSE: Self = Self(1 << 0);
+    /// Also log the PRBS result of each delay setting at debug level
+    pub const BE_MOREVERBOSE: Self = Self(1 << 1);
+    /// Tune the input delays of the baseband processor. Not supported, as
+    /// these delays are not accessible to the driver
+    pub const DO_IDELAY: Self = Self(1 << 2);
+    /// Tune the output delays of the baseband processor. Not supported, as
+    /// these delays are not accessible to the driver
+    pub const DO_ODELAY: Self = Self(1 << 3);
+
+    /// No options
+    pub const fn empty() -> Self {
+        Self(0)
+    }
+    /// Raw value of the flags
+    pub const fn bits(&self) -> u8 {
+        self.0
+    }
+    /// Returns true if all the options in `other` are set
+    pub const fn contains(&self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+impl core::ops::BitOr for DigTuneFlags {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+}
+impl core::ops::BitOrAssign for DigTuneFlags {
+    fn bitor_assign(&mut self, other: Self) {
+        self.0 |= other.0;
+    }
+}
+
+/// Delays chosen for one direction of the digital interface, see `dig_tune`
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct DigTuneDelay {
+    /// Clock delay, 0 to 15
+    pub clock_delay: u8,
+    /// Data delay, 0 to 15
+    pub data_delay: u8,
+    /// Number of consecutive passing delay settings in the window the delays
+    /// were chosen from. A narrow window indicates little timing margin
+    pub window: u8,
+}
+
+/// Outcome of `dig_tune`, for each direction that was tuned
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct DigTuneResult {
+    /// Delays chosen for the RX interface, or `None` if it was not tuned
+    pub rx: Option<DigTuneDelay>,
+    /// Delays chosen for the TX interface, or `None` if it was not tuned
+    pub tx: Option<DigTuneDelay>,
+}
+
+/// Calibration that can be triggered on demand with `do_calib`
+///
+/// Values match the calibration control bits (register 0x016) that the no-OS
+/// driver uses to select a calibration in `ad9361_do_calib`. RX quadrature and
+/// baseband DC offset corrections are continuous tracking loops rather than
+/// one-shot calibrations, and cannot be triggered on demand
+#[derive(Clone, Copy, PartialOrd, PartialEq, Eq, Debug)]
+pub enum CalibrationType {
+    /// TX quadrature calibration
+    ///
+    /// `arg` is the RX NCO phase offset used during the calibration, in the
+    /// range 0 to 31. Pass -1 for the driver to choose the phase itself
+    TxQuad = 0x10,
+    /// RF DC offset calibration, at the current RX LO frequency
+    ///
+    /// `arg` is ignored, pass -1
+    RfDc = 0x02,
+}
+impl From<CalibrationType> for u32 {
+    fn from(cal: CalibrationType) -> u32 {
+        cal as u32
+    }
+}
+
 /// Loopback mode. When enabled, loopback (AD9361 internal) TX->RX
 pub enum LoopbackMode {
     Disabled = 0,
@@ -229,18 +577,363 @@ impl From<u8> for RfGainControlMode {
     }
 }
 
+/// Auxiliary ADC (AuxADC) source
+///
+/// The AuxADC has a single input, the AUXADC pin. The internal temperature
+/// sensor has its own measurement path, read with `get_temperature`, and
+/// the supplies have no internal path to the AuxADC, so neither can be
+/// offered as a source
+#[derive(Clone, Copy, PartialOrd, PartialEq, Eq, Debug)]
+pub enum AuxAdcSource {
+    /// The AUXADC pin. Requires an external connection
+    External,
+}
+
+/// Auxiliary DAC (AuxDAC) selection
+#[derive(Clone, Copy, PartialOrd, PartialEq, Eq, Debug)]
+pub enum AuxDac {
+    /// AUXDAC1
+    Dac1,
+    /// AUXDAC2
+    Dac2,
+}
+impl From<AuxDac> for u32 {
+    fn from(dac: AuxDac) -> u32 {
+        match dac {
+            AuxDac::Dac1 => 1,
+            AuxDac::Dac2 => 2,
+        }
+    }
+}
+
+/// Signal on the CLK_OUT pin, as the `ad9361_clkout` enum in the C driver
+#[derive(Clone, Copy, PartialOrd, PartialEq, Eq, Debug)]
+pub enum ClkOutputMode {
+    /// CLK_OUT disabled
+    Disable,
+    /// Buffered reference clock (XTALN or DCXO)
+    BufferedXtalnDcxo,
+    /// ADC clock / 2
+    AdcClkDiv2,
+    /// ADC clock / 3
+    AdcClkDiv3,
+    /// ADC clock / 4
+    AdcClkDiv4,
+    /// ADC clock / 8
+    AdcClkDiv8,
+    /// ADC clock / 16
+    AdcClkDiv16,
+}
+impl From<ClkOutputMode> for u32 {
+    fn from(mode: ClkOutputMode) -> u32 {
+        mode as u32
+    }
+}
+impl From<u32> for ClkOutputMode {
+    fn from(v: u32) -> Self {
+        match v {
+            1 => ClkOutputMode::BufferedXtalnDcxo,
+            2 => ClkOutputMode::AdcClkDiv2,
+            3 => ClkOutputMode::AdcClkDiv3,
+            4 => ClkOutputMode::AdcClkDiv4,
+            5 => ClkOutputMode::AdcClkDiv8,
+            6 => ClkOutputMode::AdcClkDiv16,
+            _ => ClkOutputMode::Disable,
+        }
+    }
+}
+
+/// Duplex mode
+#[derive(Clone, Copy, PartialOrd, PartialEq, Eq, Debug)]
+pub enum DuplexMode {
+    /// Frequency Division Duplex
+    Fdd,
+    /// Time Division Duplex
+    Tdd,
+}
+
+/// Summary of the current operating mode, see `operating_mode`
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct OperatingMode {
+    /// Duplex mode
+    pub duplex: DuplexMode,
+    /// Number of active RX and TX channels
+    pub channels: ChannelMode,
+    /// Gain control mode for RX1 and RX2
+    pub gain_control: [RfGainControlMode; 2],
+}
+impl core::fmt::Display for OperatingMode {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        let channels = match self.channels {
+            ChannelMode::OneByOne => "1R1T",
+            ChannelMode::TwoByTwo => "2R2T",
+        };
+        write!(
+            f,
+            "{:?} {}, RX1 {:?}, RX2 {:?}",
+            self.duplex, channels, self.gain_control[0], self.gain_control[1]
+        )
+    }
+}
+
+/// Product ID read from the device, see `product_id`
+///
+/// The AD9361, AD9364 and AD9363A share a single product ID, so they cannot
+/// be told apart from the product ID register
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ProductId {
+    /// AD9361, AD9364 or AD9363A
+    Ad936x,
+    /// Unrecognised product ID, holding the register value without the
+    /// revision bits
+    Unknown(u8),
+}
+
+/// Frequencies of the clock tree in Hz, see `get_clock_frequencies`
+///
+/// The RX path is BBPLL → ADC → R2 (HB3) → R1 (HB2) → CLKRF (HB1) → RX
+/// sample (FIR), and the TX path is BBPLL → DAC → T2 (HB3) → T1 (HB2) →
+/// CLKTF (HB1) → TX sample (FIR)
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ClockFrequencies {
+    /// Baseband PLL
+    pub bbpll: u32,
+    /// ADC sample clock
+    pub adc: u32,
+    /// RX HB3 output
+    pub r2: u32,
+    /// RX HB2 output
+    pub r1: u32,
+    /// RX HB1 output
+    pub clkrf: u32,
+    /// RX sampling rate
+    pub rx_sample: u32,
+    /// DAC sample clock
+    pub dac: u32,
+    /// TX HB3 input
+    pub t2: u32,
+    /// TX HB2 input
+    pub t1: u32,
+    /// TX HB1 input
+    pub clktf: u32,
+    /// TX sampling rate
+    pub tx_sample: u32,
+}
+
+/// Result of each check made by `self_test`
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct SelfTestReport {
+    /// The product ID register matches the AD9361 family
+    pub product_id: bool,
+    /// Test patterns written over SPI were read back unchanged
+    pub spi_readback: bool,
+    /// The baseband PLL is locked
+    pub bbpll_locked: bool,
+    /// The RX RF synthesizer is locked
+    pub rx_synth_locked: bool,
+    /// The TX RF synthesizer is locked
+    pub tx_synth_locked: bool,
+    /// The die temperature in degrees Celsius
+    pub temperature: f32,
+    /// The die temperature is within the plausible range
+    pub temperature_plausible: bool,
+}
+impl SelfTestReport {
+    /// Returns true if every check passed
+    pub fn passed(&self) -> bool {
+        self.product_id
+            && self.spi_readback
+            && self.bbpll_locked
+            && self.rx_synth_locked
+            && self.tx_synth_locked
+            && self.temperature_plausible
+    }
+}
+impl core::fmt::Display for SelfTestReport {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        let result = |pass: bool| if pass { "pass" } else { "FAIL" };
+        write!(
+            f,
+            "product ID {}, SPI {}, BBPLL {}, RX synth {}, TX synth {}, \
+             temperature {} ({:.1}°C)",
+            result(self.product_id),
+            result(self.spi_readback),
+            result(self.bbpll_locked),
+            result(self.rx_synth_locked),
+            result(self.tx_synth_locked),
+            result(self.temperature_plausible),
+            self.temperature
+        )
+    }
+}
+
+/// Receive gain of a channel, split into its stages, see
+/// `get_rx_gain_detailed`
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct RxGainInfo {
+    /// Total receive gain in dB
+    pub gain_db: i32,
+    /// Index into the full or LMT gain table
+    pub fgt_lmt_index: u32,
+    /// LMT (LNA, mixer and TIA) gain in dB
+    pub lmt_gain: u32,
+    /// LPF gain index
+    pub lpf_gain: u32,
+    /// Digital gain index
+    pub digital_gain: u32,
+    /// LNA gain index
+    pub lna_index: u32,
+    /// TIA gain index
+    pub tia_index: u32,
+    /// Mixer gain index
+    pub mixer_index: u32,
+}
+
+/// Full RSSI measurement, see `get_rx_rssi_raw`
+///
+/// `symbol` and `preamble` are scaled by `multiplier` (100, so 0.01 dB per
+/// unit) and give the level in dB below full scale. The preamble
+/// value is the first measurement after the RSSI restart, while the symbol
+/// value is the most recent measurement.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct RfRssi {
+    /// Receive channel the measurement was taken on
+    pub ant: u32,
+    /// Latest (symbol) RSSI, in units of -0.01 dB
+    pub symbol: u32,
+    /// Preamble RSSI, in units of -0.01 dB
+    pub preamble: u32,
+    /// Units of `symbol` and `preamble` per dB
+    pub multiplier: i32,
+    /// Measurement duration
+    pub duration: u8,
+}
+impl RfRssi {
+    /// Symbol RSSI in dB
+    pub fn symbol_db(&self) -> f32 {
+        self.symbol as f32 / -100.0
+    }
+    /// Preamble RSSI in dB
+    pub fn preamble_db(&self) -> f32 {
+        self.preamble as f32 / -100.0
+    }
+}
+
+/// TX monitor measurement of one transmit channel, see `get_tx_monitor`
+///
+/// Each transmit channel has a single monitor input (TX_MON1 or TX_MON2),
+/// which measures the power fed into it. To measure forward and reflected
+/// power for VSWR, connect the forward and reflected coupler outputs to the
+/// monitor inputs of the two channels and read both.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TxMonitor {
+    /// TX RSSI word, 9 bits, in units of 0.25 dB
+    pub rssi: u16,
+}
+impl TxMonitor {
+    /// TX RSSI in mdB
+    pub fn rssi_mdb(&self) -> u32 {
+        u32::from(self.rssi) * 250
+    }
+    /// TX RSSI in dB
+    pub fn rssi_db(&self) -> f32 {
+        f32::from(self.rssi) / 4.0
+    }
+}
+
+/// RF synthesizer divider settings, see `get_rx_lo_dividers`
+///
+/// The LO frequency is
+///
+/// f_LO = f_ref * (integer + fractional / modulus) / 2^(vco_divider + 1)
+///
+/// where f_ref is the synthesizer reference frequency. The frequency step
+/// size is f_ref / modulus / 2^(vco_divider + 1).
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct LoDividers {
+    /// Integer word, 11 bits
+    pub integer: u16,
+    /// Fractional word, 23 bits
+    pub fractional: u32,
+    /// Modulus of the fractional word
+    pub modulus: u32,
+    /// VCO divider setting. The VCO is divided by 2^(vco_divider + 1)
+    pub vco_divider: u8,
+}
+impl LoDividers {
+    /// LO frequency (in Hz) for a synthesizer reference of `reference_hz`
+    pub fn frequency(&self, reference_hz: u64) -> u64 {
+        crate::synth::rfpll_frequency(
+            reference_hz,
+            self.integer.into(),
+            self.fractional.into(),
+            self.vco_divider,
+        )
+    }
+}
+
+/// Fault flags
+///
+/// The channel overflow flags are latched by the AD9361 until they are
+/// cleared, and so report faults that occurred at any time since the previous
+/// read. The lock flags report the live status of the PLLs at the time of the
+/// read.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct FaultFlags(u8);
+impl FaultFlags {
+    /// The baseband PLL is not locked (live)
+    pub const BBPLL_UNLOCKED: Self = Self(1 << 0);
+    /// The RX RF synthesizer is not locked (live)
+    pub const RX_SYNTH_UNLOCKED: Self = Self(1 << 1);
+    /// The TX RF synthesizer is not locked (live)
+    pub const TX_SYNTH_UNLOCKED: Self = Self(1 << 2);
+    /// A digital filter stage in channel 1 has overflowed (latched)
+    pub const CH1_OVERFLOW: Self = Self(1 << 3);
+    /// A digital filter stage in channel 2 has overflowed (latched)
+    pub const CH2_OVERFLOW: Self = Self(1 << 4);
+
+    /// No faults
+    pub const fn empty() -> Self {
+        Self(0)
+    }
+    /// Raw value of the flags
+    pub const fn bits(&self) -> u8 {
+        self.0
+    }
+    /// Returns true if no faults are set
+    pub const fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+    /// Returns true if all the faults in `other` are set
+    pub const fn contains(&self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+impl core::ops::BitOr for FaultFlags {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+}
+impl core::ops::BitOrAssign for FaultFlags {
+    fn bitor_assign(&mut self, other: Self) {
+        self.0 |= other.0;
+    }
+}
+
 // ---- Internal Types ----------------------
 
 #[repr(transparent)]
-pub(crate) struct TemperatureX1000(i32);
-impl From<i32> for TemperatureX1000 {
+pub(crate) struct MilliDecibels(i32);
+impl From<i32> for MilliDecibels {
     fn from(v: i32) -> Self {
         Self(v)
     }
 }
-impl From<TemperatureX1000> for f32 {
-    fn from(t: TemperatureX1000) -> f32 {
-        (t.0 as f32) / 1000.
+impl From<MilliDecibels> for f32 {
+    fn from(d: MilliDecibels) -> f32 {
+        (d.0 as f32) / 1000.
     }
 }
 
@@ -267,3 +960,28 @@ impl From<bindings::rf_rssi> for f32 {
         rssi.symbol as f32 / -100.0 // -0.25dB / LSB, already multiplied by 25
     }
 }
+impl From<bindings::rf_rx_gain> for RxGainInfo {
+    fn from(gain: bindings::rf_rx_gain) -> RxGainInfo {
+        RxGainInfo {
+            gain_db: gain.gain_db,
+            fgt_lmt_index: gain.fgt_lmt_index,
+            lmt_gain: gain.lmt_gain,
+            lpf_gain: gain.lpf_gain,
+            digital_gain: gain.digital_gain,
+            lna_index: gain.lna_index,
+            tia_index: gain.tia_index,
+            mixer_index: gain.mixer_index,
+        }
+    }
+}
+impl From<bindings::rf_rssi> for RfRssi {
+    fn from(rssi: bindings::rf_rssi) -> RfRssi {
+        RfRssi {
+            ant: rssi.ant,
+            symbol: rssi.symbol,
+            preamble: rssi.preamble,
+            multiplier: rssi.multiplier,
+            duration: rssi.duration,
+        }
+    }
+}